@@ -0,0 +1,43 @@
+use super::CodecFormat;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base32Format {
+    Standard,
+    NoPad,
+    Hex,
+}
+
+impl FromStr for Base32Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(Base32Format::Standard),
+            "nopad" => Ok(Base32Format::NoPad),
+            "hex" => Ok(Base32Format::Hex),
+            _ => Err(anyhow::anyhow!("Invalid base32 format")),
+        }
+    }
+}
+
+impl From<Base32Format> for &str {
+    fn from(format: Base32Format) -> Self {
+        match format {
+            Base32Format::Standard => "standard",
+            Base32Format::NoPad => "nopad",
+            Base32Format::Hex => "hex",
+        }
+    }
+}
+
+impl fmt::Display for Base32Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CodecFormat for Base32Format {
+    const DEFAULT: Option<&'static str> = Some("standard");
+    const HELP: &'static str = "standard, nopad or hex";
+}
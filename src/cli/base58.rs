@@ -0,0 +1,43 @@
+use super::CodecFormat;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base58Format {
+    Bitcoin,
+    Ripple,
+    Flickr,
+}
+
+impl FromStr for Base58Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bitcoin" => Ok(Base58Format::Bitcoin),
+            "ripple" => Ok(Base58Format::Ripple),
+            "flickr" => Ok(Base58Format::Flickr),
+            _ => Err(anyhow::anyhow!("Invalid base58 format")),
+        }
+    }
+}
+
+impl From<Base58Format> for &str {
+    fn from(format: Base58Format) -> Self {
+        match format {
+            Base58Format::Bitcoin => "bitcoin",
+            Base58Format::Ripple => "ripple",
+            Base58Format::Flickr => "flickr",
+        }
+    }
+}
+
+impl fmt::Display for Base58Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CodecFormat for Base58Format {
+    const DEFAULT: Option<&'static str> = Some("bitcoin");
+    const HELP: &'static str = "bitcoin, ripple or flickr";
+}
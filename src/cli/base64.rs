@@ -9,36 +9,103 @@ use std::{fmt, str::FromStr};
 pub enum Base64SubCommand {
     #[command(name = "encode", about = "encode a string to base64")]
     Encode(Base64EncodeOpts),
-    #[command(name = "decode", about = "decode a base64 string")]
+    #[command(
+        name = "decode",
+        about = "decode a base64 string, data URIs and PEM blocks are unwrapped"
+    )]
     Decode(Base64DecodeOpts),
+    #[command(
+        name = "inspect",
+        about = "show which encodings a string is valid under and what it decodes to"
+    )]
+    Inspect(Base64InspectOpts),
 }
 #[derive(Debug, Parser)]
 pub struct Base64EncodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
 
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
+
+    /// padded or unpadded, defaults to padded for standard and unpadded for urlsafe
+    #[arg(long, value_parser = parse_base64_padding)]
+    pub padding: Option<Base64Padding>,
+
+    /// wrap encoded lines at the given column (76 for MIME, 64 for PEM)
+    #[arg(long)]
+    pub wrap: Option<usize>,
+
+    /// emit a data:<mime>;base64,... URI
+    #[arg(long, conflicts_with_all = ["format", "padding", "wrap", "pem"])]
+    pub data_uri: bool,
+
+    /// MIME type for --data-uri, sniffed from the input when omitted
+    #[arg(long, requires = "data_uri")]
+    pub mime: Option<String>,
+
+    /// emit a PEM block with the given label, e.g. "PUBLIC KEY"
+    #[arg(long, conflicts_with_all = ["format", "padding", "wrap"])]
+    pub pem: Option<String>,
 }
 #[derive(Debug, Parser)]
 pub struct Base64DecodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
 
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    /// standard, urlsafe or auto to detect the alphabet from the input
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
+
+    /// padded, unpadded or indifferent
+    #[arg(long, value_parser = parse_base64_padding, default_value = "indifferent")]
+    pub padding: Base64Padding,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Base64Format {
     Standard,
     UrlSafe,
+    Auto,
+}
+
+#[derive(Debug, Parser)]
+pub struct Base64InspectOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+}
+
+// Wrapper around the encoded text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Base64Armor {
+    None,
+    // MIME type, sniffed from the input when None
+    DataUri(Option<String>),
+    // PEM label
+    Pem(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base64Padding {
+    Padded,
+    Unpadded,
+    Indifferent,
 }
 
 fn parse_base64_format(format: &str) -> Result<Base64Format, anyhow::Error> {
     format.parse()
 }
 
+fn parse_base64_padding(padding: &str) -> Result<Base64Padding, anyhow::Error> {
+    padding.parse()
+}
+
 impl FromStr for Base64Format {
     type Err = anyhow::Error;
 
@@ -46,6 +113,7 @@ impl FromStr for Base64Format {
         match s {
             "standard" => Ok(Base64Format::Standard),
             "urlsafe" => Ok(Base64Format::UrlSafe),
+            "auto" => Ok(Base64Format::Auto),
             _ => Err(anyhow::anyhow!("Invalid base64 format")),
         }
     }
@@ -56,6 +124,7 @@ impl From<Base64Format> for &str {
         match format {
             Base64Format::Standard => "standard",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::Auto => "auto",
         }
     }
 }
@@ -66,19 +135,69 @@ impl fmt::Display for Base64Format {
     }
 }
 
+impl FromStr for Base64Padding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "padded" => Ok(Base64Padding::Padded),
+            "unpadded" => Ok(Base64Padding::Unpadded),
+            "indifferent" => Ok(Base64Padding::Indifferent),
+            _ => Err(anyhow::anyhow!("Invalid base64 padding")),
+        }
+    }
+}
+
+impl From<Base64Padding> for &str {
+    fn from(padding: Base64Padding) -> Self {
+        match padding {
+            Base64Padding::Padded => "padded",
+            Base64Padding::Unpadded => "unpadded",
+            Base64Padding::Indifferent => "indifferent",
+        }
+    }
+}
+
+impl fmt::Display for Base64Padding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
 impl CmdExecutor for Base64EncodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_encode(&self.input, self.format)?;
-        println!("{}", ret);
-        Ok(())
+        let armor = match (self.data_uri, self.pem) {
+            (true, _) => Base64Armor::DataUri(self.mime),
+            (false, Some(label)) => Base64Armor::Pem(label),
+            (false, None) => Base64Armor::None,
+        };
+        crate::process_encode(
+            &self.input,
+            &self.output,
+            self.format,
+            self.padding,
+            self.wrap,
+            armor,
+        )
     }
 }
 
 impl CmdExecutor for Base64DecodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let decoded = crate::process_decode(&self.input, self.format)?;
-        let ret = String::from_utf8(decoded)?;
-        println!("{}", ret);
+        crate::process_decode(&self.input, &self.output, self.format, self.padding)
+    }
+}
+
+impl CmdExecutor for Base64InspectOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let inspections = crate::process_inspect(&self.input)?;
+        if inspections.is_empty() {
+            anyhow::bail!("Input is not valid under any known encoding");
+        }
+        for inspection in inspections {
+            println!("== {} ==", inspection.encoding);
+            println!("{}\n", crate::render_decoded(&inspection.decoded));
+        }
         Ok(())
     }
 }
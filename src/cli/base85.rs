@@ -0,0 +1,40 @@
+use super::CodecFormat;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base85Format {
+    Ascii85,
+    Z85,
+}
+
+impl FromStr for Base85Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii85" => Ok(Base85Format::Ascii85),
+            "z85" => Ok(Base85Format::Z85),
+            _ => Err(anyhow::anyhow!("Invalid base85 format")),
+        }
+    }
+}
+
+impl From<Base85Format> for &str {
+    fn from(format: Base85Format) -> Self {
+        match format {
+            Base85Format::Ascii85 => "ascii85",
+            Base85Format::Z85 => "z85",
+        }
+    }
+}
+
+impl fmt::Display for Base85Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CodecFormat for Base85Format {
+    const DEFAULT: Option<&'static str> = Some("ascii85");
+    const HELP: &'static str = "ascii85 or z85";
+}
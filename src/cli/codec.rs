@@ -0,0 +1,57 @@
+use super::verify_file;
+use crate::{CmdExecutor, Codec};
+use clap::Parser;
+use std::{fmt, str::FromStr};
+
+/// The format enum of a subcommand built on [`Codec`], e.g. `rcli base32`.
+pub trait CodecFormat:
+    Codec + FromStr<Err = anyhow::Error> + fmt::Debug + Clone + Send + Sync + 'static
+{
+    // value of `--format` when it is not given, None makes it required
+    const DEFAULT: Option<&'static str>;
+    // help text of `--format`, listing the accepted values
+    const HELP: &'static str;
+}
+
+#[derive(Debug, Parser)]
+pub enum CodecSubCommand<F: CodecFormat> {
+    #[command(name = "encode", about = "encode the input")]
+    Encode(CodecOpts<F>),
+    #[command(name = "decode", about = "decode the input")]
+    Decode(CodecOpts<F>),
+}
+
+#[derive(Debug, Parser)]
+pub struct CodecOpts<F: CodecFormat> {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    #[arg(
+        long,
+        value_parser = parse_codec_format::<F>,
+        default_value = F::DEFAULT,
+        required = F::DEFAULT.is_none(),
+        help = F::HELP
+    )]
+    pub format: F,
+}
+
+fn parse_codec_format<F: CodecFormat>(format: &str) -> Result<F, anyhow::Error> {
+    format.parse()
+}
+
+impl<F: CodecFormat> CmdExecutor for CodecSubCommand<F> {
+    async fn execute(self) -> anyhow::Result<()> {
+        match self {
+            CodecSubCommand::Encode(opts) => {
+                crate::process_codec_encode(&opts.input, &opts.output, opts.format)
+            }
+            CodecSubCommand::Decode(opts) => {
+                crate::process_codec_decode(&opts.input, &opts.output, opts.format)
+            }
+        }
+    }
+}
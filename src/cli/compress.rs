@@ -0,0 +1,130 @@
+use super::{verify_file, verify_path};
+use crate::CmdExecutor;
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+use std::{fmt, path::PathBuf, str::FromStr};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExecutor)]
+pub enum CompressSubCommand {
+    #[command(name = "compress", about = "compress a file")]
+    Compress(CompressOpts),
+    #[command(name = "decompress", about = "decompress a file")]
+    Decompress(DecompressOpts),
+    #[command(
+        name = "dir",
+        about = "precompress every file in a directory for rcli http serve"
+    )]
+    Dir(CompressDirOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct CompressOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    /// gzip, deflate, brotli or zstd
+    #[arg(long, value_parser = parse_compression_format, default_value = "gzip")]
+    pub format: CompressionFormat,
+
+    /// 0-9 for gzip/deflate, 0-11 for brotli, 1-22 for zstd
+    #[arg(short, long)]
+    pub level: Option<u32>,
+}
+
+#[derive(Debug, Parser)]
+pub struct DecompressOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    /// gzip, deflate, brotli or zstd
+    #[arg(long, value_parser = parse_compression_format, default_value = "gzip")]
+    pub format: CompressionFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CompressDirOpts {
+    #[arg(short, long, value_parser = verify_path, default_value = ".")]
+    pub dir: PathBuf,
+
+    /// formats to produce, repeat for several
+    #[arg(
+        long,
+        value_parser = parse_compression_format,
+        default_values = ["gzip", "deflate", "brotli", "zstd"]
+    )]
+    pub format: Vec<CompressionFormat>,
+
+    #[arg(short, long)]
+    pub level: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    Gzip,
+    // zlib wrapped, which is what HTTP calls deflate
+    Deflate,
+    Brotli,
+    Zstd,
+}
+
+fn parse_compression_format(format: &str) -> Result<CompressionFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl FromStr for CompressionFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(CompressionFormat::Gzip),
+            "deflate" => Ok(CompressionFormat::Deflate),
+            "brotli" => Ok(CompressionFormat::Brotli),
+            "zstd" => Ok(CompressionFormat::Zstd),
+            _ => Err(anyhow::anyhow!("Invalid compression format")),
+        }
+    }
+}
+
+impl From<CompressionFormat> for &str {
+    fn from(format: CompressionFormat) -> Self {
+        match format {
+            CompressionFormat::Gzip => "gzip",
+            CompressionFormat::Deflate => "deflate",
+            CompressionFormat::Brotli => "brotli",
+            CompressionFormat::Zstd => "zstd",
+        }
+    }
+}
+
+impl fmt::Display for CompressionFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExecutor for CompressOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_compress(&self.input, &self.output, self.format, self.level)
+    }
+}
+
+impl CmdExecutor for DecompressOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        crate::process_decompress(&self.input, &self.output, self.format)
+    }
+}
+
+impl CmdExecutor for CompressDirOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let written = crate::process_precompress(&self.dir, &self.format, self.level)?;
+        eprintln!("Wrote {} compressed files", written.len());
+        Ok(())
+    }
+}
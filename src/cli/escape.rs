@@ -0,0 +1,49 @@
+use super::CodecFormat;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeFormat {
+    UrlComponent,
+    Url,
+    Form,
+    Html,
+    Json,
+}
+
+impl FromStr for EscapeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "url-component" => Ok(EscapeFormat::UrlComponent),
+            "url" => Ok(EscapeFormat::Url),
+            "form" => Ok(EscapeFormat::Form),
+            "html" => Ok(EscapeFormat::Html),
+            "json" => Ok(EscapeFormat::Json),
+            _ => Err(anyhow::anyhow!("Invalid escape format")),
+        }
+    }
+}
+
+impl From<EscapeFormat> for &str {
+    fn from(format: EscapeFormat) -> Self {
+        match format {
+            EscapeFormat::UrlComponent => "url-component",
+            EscapeFormat::Url => "url",
+            EscapeFormat::Form => "form",
+            EscapeFormat::Html => "html",
+            EscapeFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for EscapeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CodecFormat for EscapeFormat {
+    const DEFAULT: Option<&'static str> = None;
+    const HELP: &'static str = "url-component, url, form, html or json";
+}
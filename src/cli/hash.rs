@@ -0,0 +1,148 @@
+use super::verify_file;
+use crate::CmdExecutor;
+use clap::Parser;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+pub struct HashOpts {
+    #[arg(value_parser = verify_file, default_value = "-", help = "Files to hash, - for stdin")]
+    pub files: Vec<String>,
+    #[arg(short, long, value_parser = parse_hash, default_value = "sha256")]
+    pub algorithm: HashFormat,
+    #[arg(short, long, value_parser = parse_hash_encoding, default_value = "hex")]
+    pub encoding: HashEncoding,
+    #[arg(
+        short,
+        long,
+        help = "Read checksums from the files and check them, like sha256sum -c"
+    )]
+    pub check: bool,
+}
+
+// Unkeyed digests, for `rcli hash` and checksum manifests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashFormat {
+    Blake3,
+    Sha256,
+    Sha512,
+    Sha3_256,
+    Sha3_512,
+    // 64-bit, fast but not cryptographic
+    Xxh3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashEncoding {
+    Hex,
+    Base64,
+}
+
+fn parse_hash(hash: &str) -> Result<HashFormat, anyhow::Error> {
+    hash.parse()
+}
+
+fn parse_hash_encoding(encoding: &str) -> Result<HashEncoding, anyhow::Error> {
+    encoding.parse()
+}
+
+impl FromStr for HashFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake3" => Ok(Self::Blake3),
+            "sha256" => Ok(Self::Sha256),
+            "sha512" => Ok(Self::Sha512),
+            "sha3-256" => Ok(Self::Sha3_256),
+            "sha3-512" => Ok(Self::Sha3_512),
+            "xxh3" => Ok(Self::Xxh3),
+            _ => Err(anyhow::anyhow!("Invalid hash: {}", s)),
+        }
+    }
+}
+
+impl From<HashFormat> for &'static str {
+    fn from(hash: HashFormat) -> &'static str {
+        match hash {
+            HashFormat::Blake3 => "blake3",
+            HashFormat::Sha256 => "sha256",
+            HashFormat::Sha512 => "sha512",
+            HashFormat::Sha3_256 => "sha3-256",
+            HashFormat::Sha3_512 => "sha3-512",
+            HashFormat::Xxh3 => "xxh3",
+        }
+    }
+}
+
+impl fmt::Display for HashFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl FromStr for HashEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(Self::Hex),
+            "base64" => Ok(Self::Base64),
+            _ => Err(anyhow::anyhow!("Invalid hash encoding: {}", s)),
+        }
+    }
+}
+
+impl From<HashEncoding> for &'static str {
+    fn from(encoding: HashEncoding) -> &'static str {
+        match encoding {
+            HashEncoding::Hex => "hex",
+            HashEncoding::Base64 => "base64",
+        }
+    }
+}
+
+impl fmt::Display for HashEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExecutor for HashOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if !self.check {
+            for file in &self.files {
+                let digest = crate::process_hash(file, self.algorithm, self.encoding)?;
+                println!("{}", crate::format_checksum_line(&digest, file));
+            }
+            return Ok(());
+        }
+
+        let mut failed = 0;
+        let mut unreadable = 0;
+        for file in &self.files {
+            for check in crate::process_hash_check(file, self.algorithm)? {
+                match check.status {
+                    crate::HashCheckStatus::Ok => println!("{}: OK", check.name),
+                    crate::HashCheckStatus::Failed => {
+                        failed += 1;
+                        println!("{}: FAILED", check.name);
+                    }
+                    crate::HashCheckStatus::Unreadable => {
+                        unreadable += 1;
+                        println!("{}: FAILED open or read", check.name);
+                    }
+                }
+            }
+        }
+        if unreadable > 0 {
+            eprintln!("WARNING: {} listed files could not be read", unreadable);
+        }
+        if failed > 0 {
+            eprintln!("WARNING: {} computed checksums did NOT match", failed);
+        }
+        if failed + unreadable > 0 {
+            anyhow::bail!("Checksum verification failed");
+        }
+        Ok(())
+    }
+}
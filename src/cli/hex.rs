@@ -0,0 +1,40 @@
+use super::CodecFormat;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexFormat {
+    Lower,
+    Upper,
+}
+
+impl FromStr for HexFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lower" => Ok(HexFormat::Lower),
+            "upper" => Ok(HexFormat::Upper),
+            _ => Err(anyhow::anyhow!("Invalid hex format")),
+        }
+    }
+}
+
+impl From<HexFormat> for &str {
+    fn from(format: HexFormat) -> Self {
+        match format {
+            HexFormat::Lower => "lower",
+            HexFormat::Upper => "upper",
+        }
+    }
+}
+
+impl fmt::Display for HexFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CodecFormat for HexFormat {
+    const DEFAULT: Option<&'static str> = Some("lower");
+    const HELP: &'static str = "lower or upper";
+}
@@ -0,0 +1,50 @@
+use clap::Parser;
+
+use crate::CmdExecutor;
+
+use super::verify_file;
+
+#[derive(Debug, Parser)]
+pub struct HexdumpOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    /// start at this byte offset
+    #[arg(short, long, default_value_t = 0)]
+    pub seek: u64,
+
+    /// stop after this many bytes
+    #[arg(short, long)]
+    pub length: Option<u64>,
+
+    /// bytes per line
+    #[arg(short, long, default_value_t = 16)]
+    pub cols: usize,
+
+    /// bytes per group, 0 for no grouping
+    #[arg(short, long, default_value_t = 2)]
+    pub group: usize,
+
+    /// convert a hexdump back into binary
+    #[arg(short, long)]
+    pub reverse: bool,
+}
+
+impl CmdExecutor for HexdumpOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if self.reverse {
+            return crate::process_hexdump_reverse(&self.input, &self.output);
+        }
+        crate::process_hexdump(
+            &self.input,
+            &self.output,
+            self.seek,
+            self.length,
+            self.cols,
+            self.group,
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{load_config_from_file, new_uuid, CmdExecutor};
 use anyhow::{Ok, Result};
 use chrono::{TimeDelta, Utc};
-use clap::{command, Parser};
+use clap::Parser;
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
 
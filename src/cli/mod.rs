@@ -1,12 +1,24 @@
+mod base32;
+mod base58;
 mod base64;
+mod base85;
+mod codec;
+mod compress;
 mod csv;
+mod escape;
 mod genpass;
+mod hash;
+mod hex;
+mod hexdump;
 mod http;
 mod jwt;
 mod text;
 use std::path::{Path, PathBuf};
 
-pub use self::{base64::*, csv::*, genpass::*, http::*, jwt::*, text::*};
+pub use self::{
+    base32::*, base58::*, base64::*, base85::*, codec::*, compress::*, csv::*, escape::*,
+    genpass::*, hash::*, hex::*, hexdump::*, http::*, jwt::*, text::*,
+};
 
 use chrono::{Duration, TimeDelta};
 use clap::Parser;
@@ -29,6 +41,33 @@ pub enum SubCommand {
     GenPass(GenPassOpts),
     #[command(subcommand, name = "base64", about = "Base64 encode/decode")]
     Base64(Base64SubCommand),
+    #[command(subcommand, name = "base32", about = "Base32 encode/decode")]
+    Base32(CodecSubCommand<Base32Format>),
+    #[command(subcommand, name = "base58", about = "Base58 encode/decode")]
+    Base58(CodecSubCommand<Base58Format>),
+    #[command(subcommand, name = "base85", about = "Ascii85/Z85 encode/decode")]
+    Base85(CodecSubCommand<Base85Format>),
+    #[command(subcommand, name = "hex", about = "Hex encode/decode")]
+    Hex(CodecSubCommand<HexFormat>),
+    #[command(
+        subcommand,
+        name = "escape",
+        about = "URL, form, HTML and JSON escape/unescape"
+    )]
+    Escape(CodecSubCommand<EscapeFormat>),
+    #[command(
+        subcommand,
+        name = "compress",
+        about = "gzip/deflate/brotli/zstd compress/decompress"
+    )]
+    Compress(CompressSubCommand),
+    #[command(
+        name = "hash",
+        about = "Checksum files with blake3, SHA-2, SHA-3 or xxh3"
+    )]
+    Hash(HashOpts),
+    #[command(name = "hexdump", about = "Show an xxd style hexdump, or reverse one")]
+    Hexdump(HexdumpOpts),
     #[command(subcommand, name = "text", about = "Text sign/verify")]
     Text(TextSubCommand),
     #[command(subcommand, name = "http", about = "HTTP server")]
@@ -56,10 +95,8 @@ fn verify_path(path: &str) -> anyhow::Result<PathBuf, &'static str> {
 }
 
 fn verify_exp(exp: &str) -> anyhow::Result<TimeDelta, &'static str> {
-    let duration_result = parse_duration(exp);
-
-    if duration_result.is_ok() {
-        let duration = Duration::from_std(duration_result.unwrap()).unwrap();
+    if let Ok(duration) = parse_duration(exp) {
+        let duration = Duration::from_std(duration).unwrap();
         println!("Duration: {:?}", duration);
         Ok(duration)
     } else {
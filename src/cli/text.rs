@@ -1,4 +1,7 @@
-use crate::CmdExecutor;
+use crate::{
+    protect_private_key, read_passphrase, write_key_files, Argon2Cost, CmdExecutor, HashFormat,
+    KeyFile,
+};
 
 use super::{verify_file, verify_path};
 use anyhow::{Ok, Result};
@@ -15,10 +18,19 @@ pub enum TextSubCommand {
     Verify(TextVerifyOpts),
     #[command(about = "Generate a private/public key pair")]
     Generate(TextKeyGenerateOpts),
-    #[command(about = "Encrypt a message with a shared key")]
+    #[command(about = "Encrypt a message with a shared key or to X25519 recipients")]
     Encrypt(TextEncryptOpts),
-    #[command(about = "Decrypt a message with a shared key")]
+    #[command(
+        about = "Decrypt a message or file with a shared key or an X25519 identity",
+        alias = "decrypt-file"
+    )]
     Decrypt(TextDecryptOpts),
+    #[command(about = "Encrypt a large file in authenticated chunks with a shared key")]
+    EncryptFile(TextEncryptFileOpts),
+    #[command(about = "Hash every file in a directory into a signed checksum manifest")]
+    SignManifest(TextSignManifestOpts),
+    #[command(about = "Check a signed checksum manifest against a directory")]
+    VerifyManifest(TextVerifyManifestOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -29,6 +41,41 @@ pub struct TextSignOpts {
     pub key: String,
     #[arg(short, long, value_parser = parse_format, default_value = "blake3")]
     pub format: TextSignFormat,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_signature_encoding,
+        default_value = "base64url",
+        conflicts_with = "bundle",
+        help = "base64url, base64 or hex"
+    )]
+    pub encoding: SignatureEncoding,
+    #[arg(
+        long,
+        conflicts_with = "bundle",
+        help = "Write ECDSA signatures as ASN.1 DER instead of raw r || s"
+    )]
+    pub der: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write the signature to a file instead of stdout"
+    )]
+    pub signature_file: Option<String>,
+    #[arg(
+        long,
+        help = "Write a JSON bundle with the algorithm, key fingerprint and timestamp"
+    )]
+    pub bundle: bool,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_metadata,
+        requires = "bundle",
+        value_name = "KEY=VALUE",
+        help = "Metadata to sign along with the message, repeatable"
+    )]
+    pub metadata: Vec<(String, String)>,
 }
 
 #[derive(Debug, Parser)]
@@ -37,47 +84,421 @@ pub struct TextVerifyOpts {
     pub input: String,
     #[arg(short, long, value_parser = verify_file)]
     pub key: String,
-    #[arg(short, long, value_parser = parse_format, default_value = "blake3")]
-    pub format: TextSignFormat,
-    #[arg(long)]
-    pub signature: String,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_format,
+        help = "Defaults to the bundle's algorithm, or blake3 for a bare signature"
+    )]
+    pub format: Option<TextSignFormat>,
+    #[arg(long, required_unless_present = "signature_file")]
+    pub signature: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with = "signature",
+        value_name = "PATH",
+        help = "Read a bare signature or a JSON bundle from a file"
+    )]
+    pub signature_file: Option<String>,
 }
 
 #[derive(Debug, Parser)]
 pub struct TextKeyGenerateOpts {
-    #[arg(short, long, value_parser = parse_format, default_value = "blake3")]
-    pub format: TextSignFormat,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_generate_format,
+        default_value = "blake3",
+        help = "A signing format, a cipher (chacha20, xchacha20, aes256gcm, aes256gcm-siv) or x25519"
+    )]
+    pub format: TextGenerateFormat,
     #[arg(short, long, value_parser = verify_path)]
     pub output: PathBuf,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_key_format,
+        default_value = "raw",
+        help = "raw, pem (PKCS#8/SPKI) or openssh, pem for ed25519 and ecdsa, openssh for ed25519"
+    )]
+    pub key_format: TextKeyFormat,
+    #[arg(
+        long,
+        value_parser = parse_passphrase,
+        num_args = 0..=1,
+        default_missing_value = "prompt",
+        value_name = "SOURCE",
+        help = "Encrypt the private key under a passphrase: prompt (default), env:VAR or file:PATH"
+    )]
+    pub passphrase: Option<PassphraseSource>,
+    #[arg(long, help = "Overwrite existing key files")]
+    pub force: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Parser)]
+pub struct TextSignManifestOpts {
+    #[arg(value_parser = verify_path)]
+    pub dir: PathBuf,
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: String,
+    #[arg(short, long, value_parser = parse_format, default_value = "ed25519")]
+    pub format: TextSignFormat,
+    #[arg(long, value_parser = parse_hash, default_value = "sha256")]
+    pub hash: HashFormat,
+    #[arg(
+        short,
+        long,
+        help = "Manifest to write, SHA256SUMS, B3SUMS etc. in the directory by default"
+    )]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct TextVerifyManifestOpts {
+    #[arg(value_parser = verify_path)]
+    pub dir: PathBuf,
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: String,
+    #[arg(
+        short,
+        long,
+        help = "Manifest to check, SHA256SUMS, B3SUMS etc. in the directory by default"
+    )]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextSignFormat {
     Blake3,
     Ed25519,
-    Chacha20,
+    Ed25519ph,
+    // ECDSA with SHA-256
+    EcdsaP256,
+    EcdsaSecp256k1,
+    // HMAC with a shared secret of any length, as used to sign webhooks
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+}
+
+// Keys `text generate` can write: signing keys, cipher keys, which are
+// the same for every cipher, and age identities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextGenerateFormat {
+    Sign(TextSignFormat),
+    Cipher(TextEncryptFormat),
+    // age identity/recipient pair for public-key encryption
+    X25519,
+}
+
+// How `text sign` writes a bare signature, `text verify` accepts any of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureEncoding {
+    #[default]
+    Base64Url,
+    Base64,
+    Hex,
+}
+
+// How `text generate` writes a key pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextKeyFormat {
+    Raw,
+    Pem,
+    OpenSsh,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextEncryptFormat {
+    ChaCha20Poly1305,
+    // 192-bit nonces, safe to pick at random for any number of messages
+    #[default]
+    XChaCha20Poly1305,
+    Aes256Gcm,
+    // nonce misuse resistant
+    Aes256GcmSiv,
 }
 
 #[derive(Debug, Parser)]
 pub struct TextEncryptOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(
+        short,
+        long,
+        value_parser = verify_file,
+        required_unless_present_any = ["passphrase", "recipient"]
+    )]
+    pub key: Option<String>,
+    #[arg(
+        long,
+        value_parser = parse_passphrase,
+        num_args = 0..=1,
+        default_missing_value = "prompt",
+        conflicts_with = "key",
+        value_name = "SOURCE",
+        help = "Use a passphrase instead of a key file: prompt (default), env:VAR or file:PATH"
+    )]
+    pub passphrase: Option<PassphraseSource>,
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["key", "passphrase", "cipher"],
+        value_name = "RECIPIENT",
+        help = "Encrypt to an age1 X25519 public key or a file of them, repeatable"
+    )]
+    pub recipient: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = Argon2Cost::default().memory_kib,
+        help = "Argon2id memory cost in KiB for --passphrase"
+    )]
+    pub argon2_memory: u32,
+    #[arg(
+        long,
+        default_value_t = Argon2Cost::default().iterations,
+        help = "Argon2id iterations for --passphrase"
+    )]
+    pub argon2_iterations: u32,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_cipher,
+        default_value = "xchacha20",
+        help = "chacha20, xchacha20, aes256gcm or aes256gcm-siv"
+    )]
+    pub cipher: TextEncryptFormat,
+    #[arg(
+        long,
+        conflicts_with = "recipient",
+        help = "Associated data to authenticate but not encrypt, e.g. a record id"
+    )]
+    pub aad: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with_all = ["aad", "recipient"],
+        help = "Read the associated data from a file"
+    )]
+    pub aad_file: Option<String>,
+    #[arg(
+        long,
+        help = "Write the binary envelope instead of an armored PEM block"
+    )]
+    pub binary: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct TextDecryptOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
-    #[arg(short, long, value_parser = verify_file)]
-    pub key: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(
+        short,
+        long,
+        value_parser = verify_file,
+        required_unless_present_any = ["passphrase", "identity"]
+    )]
+    pub key: Option<String>,
+    #[arg(
+        long,
+        value_parser = parse_passphrase,
+        num_args = 0..=1,
+        default_missing_value = "prompt",
+        conflicts_with = "key",
+        value_name = "SOURCE",
+        help = "Use a passphrase instead of a key file: prompt (default), env:VAR or file:PATH"
+    )]
+    pub passphrase: Option<PassphraseSource>,
+    #[arg(
+        long,
+        conflicts_with = "identity",
+        help = "Associated data to authenticate but not encrypt, e.g. a record id"
+    )]
+    pub aad: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with_all = ["aad", "identity"],
+        help = "Read the associated data from a file"
+    )]
+    pub aad_file: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with_all = ["key", "passphrase"],
+        help = "Decrypt an age file with the X25519 identities in this file"
+    )]
+    pub identity: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct TextEncryptFileOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(
+        short,
+        long,
+        value_parser = verify_file,
+        required_unless_present_any = ["passphrase", "recipient"]
+    )]
+    pub key: Option<String>,
+    #[arg(
+        long,
+        value_parser = parse_passphrase,
+        num_args = 0..=1,
+        default_missing_value = "prompt",
+        conflicts_with = "key",
+        value_name = "SOURCE",
+        help = "Use a passphrase instead of a key file: prompt (default), env:VAR or file:PATH"
+    )]
+    pub passphrase: Option<PassphraseSource>,
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["key", "passphrase", "cipher"],
+        value_name = "RECIPIENT",
+        help = "Encrypt to an age1 X25519 public key or a file of them, repeatable"
+    )]
+    pub recipient: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = Argon2Cost::default().memory_kib,
+        help = "Argon2id memory cost in KiB for --passphrase"
+    )]
+    pub argon2_memory: u32,
+    #[arg(
+        long,
+        default_value_t = Argon2Cost::default().iterations,
+        help = "Argon2id iterations for --passphrase"
+    )]
+    pub argon2_iterations: u32,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_cipher,
+        default_value = "xchacha20",
+        help = "chacha20, xchacha20, aes256gcm or aes256gcm-siv"
+    )]
+    pub cipher: TextEncryptFormat,
+    #[arg(
+        long,
+        conflicts_with = "recipient",
+        help = "Associated data to authenticate but not encrypt, e.g. a record id"
+    )]
+    pub aad: Option<String>,
+    #[arg(
+        long,
+        value_parser = verify_file,
+        conflicts_with_all = ["aad", "recipient"],
+        help = "Read the associated data from a file"
+    )]
+    pub aad_file: Option<String>,
+}
+
+// Where the key for encrypt/decrypt comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextKey {
+    File(String),
+    Passphrase(PassphraseSource),
+    // age1 public keys, or files of them, to encrypt to
+    Recipients(Vec<String>),
+    // age identity file to decrypt with
+    Identity(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassphraseSource {
+    // ask on the terminal without echo
+    Prompt,
+    Env(String),
+    File(String),
 }
 
 fn parse_format(format: &str) -> Result<TextSignFormat, anyhow::Error> {
     format.parse()
 }
 
+fn parse_generate_format(format: &str) -> Result<TextGenerateFormat, anyhow::Error> {
+    format.parse()
+}
+
+fn parse_key_format(format: &str) -> Result<TextKeyFormat, anyhow::Error> {
+    format.parse()
+}
+
+fn parse_signature_encoding(encoding: &str) -> Result<SignatureEncoding, anyhow::Error> {
+    encoding.parse()
+}
+
+fn parse_hash(hash: &str) -> Result<HashFormat, anyhow::Error> {
+    hash.parse()
+}
+
+fn parse_cipher(cipher: &str) -> Result<TextEncryptFormat, anyhow::Error> {
+    cipher.parse()
+}
+
+fn parse_passphrase(source: &str) -> Result<PassphraseSource, anyhow::Error> {
+    source.parse()
+}
+
+fn parse_metadata(entry: &str) -> Result<(String, String), anyhow::Error> {
+    match entry.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(anyhow::anyhow!("Metadata must be KEY=VALUE, got {}", entry)),
+    }
+}
+
+impl TextKey {
+    // clap makes sure exactly one is given
+    fn new(
+        key: Option<String>,
+        passphrase: Option<PassphraseSource>,
+        recipients: Vec<String>,
+        identity: Option<String>,
+    ) -> Self {
+        match (key, passphrase, identity) {
+            _ if !recipients.is_empty() => TextKey::Recipients(recipients),
+            (_, _, Some(path)) => TextKey::Identity(path),
+            (_, Some(source), None) => TextKey::Passphrase(source),
+            (Some(path), None, None) => TextKey::File(path),
+            (None, None, None) => unreachable!("--key, --passphrase or --recipient is required"),
+        }
+    }
+}
+
+// --aad and --aad-file conflict, so at most one is set
+fn read_aad(aad: Option<String>, aad_file: Option<String>) -> Result<Vec<u8>> {
+    match (aad, aad_file) {
+        (Some(aad), _) => Ok(aad.into_bytes()),
+        (None, Some(path)) => Ok(fs::read(path)?),
+        (None, None) => Ok(Vec::new()),
+    }
+}
+
+impl FromStr for PassphraseSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            _ if s == "prompt" => Ok(Self::Prompt),
+            Some(("env", name)) if !name.is_empty() => Ok(Self::Env(name.to_string())),
+            Some(("file", path)) if !path.is_empty() => Ok(Self::File(path.to_string())),
+            _ => Err(anyhow::anyhow!(
+                "Invalid passphrase source: {}, use prompt, env:VAR or file:PATH",
+                s
+            )),
+        }
+    }
+}
+
 impl FromStr for TextSignFormat {
     type Err = anyhow::Error;
 
@@ -85,7 +506,12 @@ impl FromStr for TextSignFormat {
         match s {
             "blake3" => Ok(Self::Blake3),
             "ed25519" => Ok(Self::Ed25519),
-            "chacha20" => Ok(Self::Chacha20),
+            "ed25519ph" => Ok(Self::Ed25519ph),
+            "ecdsa-p256" => Ok(Self::EcdsaP256),
+            "ecdsa-secp256k1" => Ok(Self::EcdsaSecp256k1),
+            "hmac-sha256" => Ok(Self::HmacSha256),
+            "hmac-sha384" => Ok(Self::HmacSha384),
+            "hmac-sha512" => Ok(Self::HmacSha512),
             _ => Err(anyhow::anyhow!("Invalid format: {}", s)),
         }
     }
@@ -96,7 +522,12 @@ impl From<TextSignFormat> for &'static str {
         match format {
             TextSignFormat::Blake3 => "blake3",
             TextSignFormat::Ed25519 => "ed25519",
-            TextSignFormat::Chacha20 => "chacha20",
+            TextSignFormat::Ed25519ph => "ed25519ph",
+            TextSignFormat::EcdsaP256 => "ecdsa-p256",
+            TextSignFormat::EcdsaSecp256k1 => "ecdsa-secp256k1",
+            TextSignFormat::HmacSha256 => "hmac-sha256",
+            TextSignFormat::HmacSha384 => "hmac-sha384",
+            TextSignFormat::HmacSha512 => "hmac-sha512",
         }
     }
 }
@@ -107,18 +538,154 @@ impl fmt::Display for TextSignFormat {
     }
 }
 
+impl FromStr for TextGenerateFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x25519" => Ok(Self::X25519),
+            _ => s
+                .parse()
+                .map(Self::Sign)
+                .or_else(|_| s.parse().map(Self::Cipher))
+                .map_err(|_| anyhow::anyhow!("Invalid format: {}", s)),
+        }
+    }
+}
+
+impl From<TextGenerateFormat> for &'static str {
+    fn from(format: TextGenerateFormat) -> &'static str {
+        match format {
+            TextGenerateFormat::Sign(format) => format.into(),
+            TextGenerateFormat::Cipher(format) => format.into(),
+            TextGenerateFormat::X25519 => "x25519",
+        }
+    }
+}
+
+impl fmt::Display for TextGenerateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl FromStr for TextKeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "pem" => Ok(Self::Pem),
+            "openssh" => Ok(Self::OpenSsh),
+            _ => Err(anyhow::anyhow!("Invalid key format: {}", s)),
+        }
+    }
+}
+
+impl FromStr for SignatureEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64url" => Ok(Self::Base64Url),
+            "base64" => Ok(Self::Base64),
+            "hex" => Ok(Self::Hex),
+            _ => Err(anyhow::anyhow!("Invalid signature encoding: {}", s)),
+        }
+    }
+}
+
+impl From<SignatureEncoding> for &'static str {
+    fn from(encoding: SignatureEncoding) -> &'static str {
+        match encoding {
+            SignatureEncoding::Base64Url => "base64url",
+            SignatureEncoding::Base64 => "base64",
+            SignatureEncoding::Hex => "hex",
+        }
+    }
+}
+
+impl fmt::Display for SignatureEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<TextKeyFormat> for &'static str {
+    fn from(format: TextKeyFormat) -> &'static str {
+        match format {
+            TextKeyFormat::Raw => "raw",
+            TextKeyFormat::Pem => "pem",
+            TextKeyFormat::OpenSsh => "openssh",
+        }
+    }
+}
+
+impl fmt::Display for TextKeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl FromStr for TextEncryptFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chacha20" => Ok(Self::ChaCha20Poly1305),
+            "xchacha20" => Ok(Self::XChaCha20Poly1305),
+            "aes256gcm" => Ok(Self::Aes256Gcm),
+            "aes256gcm-siv" => Ok(Self::Aes256GcmSiv),
+            _ => Err(anyhow::anyhow!("Invalid cipher: {}", s)),
+        }
+    }
+}
+
+impl From<TextEncryptFormat> for &'static str {
+    fn from(format: TextEncryptFormat) -> &'static str {
+        match format {
+            TextEncryptFormat::ChaCha20Poly1305 => "chacha20",
+            TextEncryptFormat::XChaCha20Poly1305 => "xchacha20",
+            TextEncryptFormat::Aes256Gcm => "aes256gcm",
+            TextEncryptFormat::Aes256GcmSiv => "aes256gcm-siv",
+        }
+    }
+}
+
+impl fmt::Display for TextEncryptFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
 impl CmdExecutor for TextSignOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let sign = crate::process_text_sign(&self.input, &self.key, self.format).await?;
-        println!("{}", sign);
+        let sign = if self.bundle {
+            let metadata = self.metadata.into_iter().collect();
+            crate::process_text_sign_bundle(&self.input, &self.key, self.format, metadata)
+                .await?
+                .to_json()?
+        } else {
+            crate::process_text_sign(&self.input, &self.key, self.format, self.encoding, self.der)
+                .await?
+        };
+        match self.signature_file {
+            Some(path) => fs::write(path, format!("{}\n", sign))?,
+            None => println!("{}", sign),
+        }
         Ok(())
     }
 }
 
 impl CmdExecutor for TextVerifyOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ret = crate::process_text_verify(&self.input, &self.key, self.format, &self.signature)
-            .await?;
+        let signature = match (self.signature, self.signature_file) {
+            (Some(signature), _) => signature,
+            (None, Some(path)) => fs::read_to_string(path)?,
+            (None, None) => unreachable!("--signature or --signature-file is required"),
+        };
+        let ret =
+            crate::process_text_verify(&self.input, &self.key, self.format, &signature).await?;
         println!("{}", ret);
         Ok(())
     }
@@ -126,38 +693,154 @@ impl CmdExecutor for TextVerifyOpts {
 
 impl CmdExecutor for TextKeyGenerateOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let key = crate::process_text_key_generate(self.format)?;
-        match self.format {
-            crate::TextSignFormat::Blake3 => {
-                let name = self.output.join("blake3.txt");
-                fs::write(name, &key[0])?;
+        let key = crate::process_text_key_generate(self.format, self.key_format)?;
+        let name = &self.output;
+        let mut files = match self.format {
+            TextGenerateFormat::Sign(TextSignFormat::Blake3) => {
+                vec![KeyFile::private(name.join("blake3.txt"), key[0].clone())]
+            }
+            TextGenerateFormat::Sign(TextSignFormat::Ed25519 | TextSignFormat::Ed25519ph) => {
+                let (sk, pk) = match self.key_format {
+                    TextKeyFormat::Raw => ("ed25519.sk", "ed25519.pk"),
+                    TextKeyFormat::Pem => ("ed25519.sk.pem", "ed25519.pk.pem"),
+                    TextKeyFormat::OpenSsh => ("id_ed25519", "id_ed25519.pub"),
+                };
+                vec![
+                    KeyFile::private(name.join(sk), key[0].clone()),
+                    KeyFile::public(name.join(pk), key[1].clone()),
+                ]
+            }
+            TextGenerateFormat::Cipher(_) => {
+                vec![KeyFile::private(
+                    name.join(format!("{}.key", self.format)),
+                    key[0].clone(),
+                )]
             }
-            crate::TextSignFormat::Ed25519 => {
-                let name = &self.output;
-                fs::write(name.join("ed25519.sk"), &key[0])?;
-                fs::write(name.join("ed25519.pk"), &key[1])?;
+            TextGenerateFormat::X25519 => vec![
+                KeyFile::private(name.join("x25519.sk"), key[0].clone()),
+                KeyFile::public(name.join("x25519.pk"), key[1].clone()),
+            ],
+            TextGenerateFormat::Sign(
+                TextSignFormat::EcdsaP256 | TextSignFormat::EcdsaSecp256k1,
+            ) => {
+                let ext = match self.key_format {
+                    TextKeyFormat::Pem => ".pem",
+                    _ => "",
+                };
+                vec![
+                    KeyFile::private(
+                        name.join(format!("{}.sk{}", self.format, ext)),
+                        key[0].clone(),
+                    ),
+                    KeyFile::public(
+                        name.join(format!("{}.pk{}", self.format, ext)),
+                        key[1].clone(),
+                    ),
+                ]
             }
-            crate::TextSignFormat::Chacha20 => {
-                let name = &self.output;
-                fs::write(name.join("chacha20.key"), &key[0])?;
+            TextGenerateFormat::Sign(
+                TextSignFormat::HmacSha256
+                | TextSignFormat::HmacSha384
+                | TextSignFormat::HmacSha512,
+            ) => {
+                vec![KeyFile::private(
+                    name.join(format!("{}.key", self.format)),
+                    key[0].clone(),
+                )]
+            }
+        };
+        if let Some(source) = &self.passphrase {
+            let passphrase = read_passphrase(source, true)?;
+            for file in files.iter_mut().filter(|file| file.private) {
+                file.data = protect_private_key(&file.data, &passphrase, Argon2Cost::default())?;
             }
         }
-        Ok(())
+        write_key_files(&files, self.force)
     }
 }
 
 impl CmdExecutor for TextEncryptOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let ciphertext = crate::process_text_encrypt(&self.input, &self.key).await?;
-        println!("\nciphertext: {}", ciphertext);
-        Ok(())
+        let key = TextKey::new(self.key, self.passphrase, self.recipient, None);
+        let cost = Argon2Cost {
+            memory_kib: self.argon2_memory,
+            iterations: self.argon2_iterations,
+        };
+        crate::process_text_encrypt(
+            &self.input,
+            &self.output,
+            &key,
+            cost,
+            self.cipher,
+            &read_aad(self.aad, self.aad_file)?,
+            !self.binary,
+        )
+        .await
     }
 }
 
 impl CmdExecutor for TextDecryptOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let plaintext = crate::process_text_decrypt(&self.input, &self.key).await?;
-        println!("\nplaintext: {}", plaintext);
+        let key = TextKey::new(self.key, self.passphrase, Vec::new(), self.identity);
+        let aad = read_aad(self.aad, self.aad_file)?;
+        crate::process_text_decrypt(&self.input, &self.output, &key, &aad).await
+    }
+}
+
+impl CmdExecutor for TextEncryptFileOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let key = TextKey::new(self.key, self.passphrase, self.recipient, None);
+        let cost = Argon2Cost {
+            memory_kib: self.argon2_memory,
+            iterations: self.argon2_iterations,
+        };
+        let aad = read_aad(self.aad, self.aad_file)?;
+        crate::process_text_encrypt_file(&self.input, &self.output, &key, cost, self.cipher, &aad)
+    }
+}
+
+impl CmdExecutor for TextSignManifestOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let manifest = crate::process_text_sign_manifest(
+            &self.dir,
+            &self.key,
+            self.format,
+            self.hash,
+            self.manifest.as_deref(),
+        )
+        .await?;
+        println!(
+            "Wrote {} and {}.sig",
+            manifest.display(),
+            manifest.display()
+        );
+        Ok(())
+    }
+}
+
+impl CmdExecutor for TextVerifyManifestOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let report =
+            crate::process_text_verify_manifest(&self.dir, &self.key, self.manifest.as_deref())
+                .await?;
+        for name in &report.missing {
+            println!("MISSING  {}", name);
+        }
+        for name in &report.modified {
+            println!("MODIFIED {}", name);
+        }
+        for name in &report.extra {
+            println!("EXTRA    {}", name);
+        }
+        if !report.is_ok() {
+            anyhow::bail!(
+                "{} missing, {} modified, {} extra files",
+                report.missing.len(),
+                report.modified.len(),
+                report.extra.len()
+            );
+        }
+        println!("OK: signature valid, {} files verified", report.verified);
         Ok(())
     }
 }
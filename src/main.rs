@@ -1,10 +1,15 @@
 // rcli csv -i input.csv -o output.json -d ','
 use clap::Parser;
 use rcli::{CmdExecutor, Opts};
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+    // logs go to stderr so they never mix with data written to stdout
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
     let opts: Opts = Opts::parse();
     opts.cmd.execute().await?;
     anyhow::Ok(())
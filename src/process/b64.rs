@@ -1,54 +1,646 @@
-use std::io::Read;
+use std::io::{self, BufRead, BufReader, Read, Write};
 
-use crate::{get_reader, Base64Format};
+use crate::{
+    get_reader, get_writer, read_full, Base64Armor, Base64Format, Base64Padding, SkipWhitespace,
+};
 use anyhow::Ok;
 use base64::{
-    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
-    Engine as _,
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    read::DecoderReader,
+    write::EncoderWriter,
 };
+use tracing::info;
+
+const PEM_WIDTH: usize = 64;
+// enough for infer to recognize every format it knows about
+const SNIFF_LEN: usize = 8192;
+// upper bound for a data URI header or a PEM header/footer line
+const MAX_ARMOR_LINE: u64 = 1024;
+
+pub fn process_encode(
+    input: &str,
+    output: &str,
+    format: Base64Format,
+    padding: Option<Base64Padding>,
+    wrap: Option<usize>,
+    armor: Base64Armor,
+) -> anyhow::Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    match armor {
+        Base64Armor::None => encode_stream(&mut reader, &mut writer, format, padding, wrap)?,
+        _ => encode_armored(&mut reader, &mut writer, &armor)?,
+    };
+    // keep the terminal prompt on its own line, PEM files end with a newline
+    if output == "-" || matches!(armor, Base64Armor::Pem(_)) {
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
 
-pub fn process_encode(input: &str, format: Base64Format) -> anyhow::Result<String> {
+pub fn process_decode(
+    input: &str,
+    output: &str,
+    format: Base64Format,
+    padding: Base64Padding,
+) -> anyhow::Result<()> {
     let mut reader = get_reader(input)?;
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
+    let mut writer = get_writer(output)?;
+    match decode_armored(&mut reader, &mut writer, format, padding)? {
+        Base64Armor::DataUri(mime) => info!("Unwrapped data URI with MIME type {:?}", mime),
+        Base64Armor::Pem(label) => info!("Unwrapped PEM block {:?}", label),
+        Base64Armor::None => {}
+    }
+    writer.flush()?;
+    Ok(())
+}
 
-    let encoded = match format {
-        Base64Format::Standard => STANDARD.encode(&buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(&buf),
+/// Encode into a data URI or a PEM block, both with the standard padded alphabet.
+pub fn encode_armored(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    armor: &Base64Armor,
+) -> anyhow::Result<u64> {
+    let n = match armor {
+        Base64Armor::None => encode_stream(reader, writer, Base64Format::Standard, None, None)?,
+        Base64Armor::DataUri(mime) => {
+            let mut head = vec![0u8; SNIFF_LEN];
+            let len = read_full(reader, &mut head)?;
+            head.truncate(len);
+            let mime = match mime {
+                Some(mime) => mime,
+                None => sniff_mime(&head),
+            };
+            write!(writer, "data:{};base64,", mime)?;
+            let mut reader = io::Cursor::new(head).chain(reader);
+            encode_stream(&mut reader, writer, Base64Format::Standard, None, None)?
+        }
+        Base64Armor::Pem(label) => {
+            verify_pem_label(label)?;
+            writeln!(writer, "-----BEGIN {}-----", label)?;
+            let n = encode_stream(
+                reader,
+                writer,
+                Base64Format::Standard,
+                None,
+                Some(PEM_WIDTH),
+            )?;
+            if n > 0 {
+                writeln!(writer)?;
+            }
+            write!(writer, "-----END {}-----", label)?;
+            n
+        }
     };
-    Ok(encoded)
+    Ok(n)
+}
+
+/// Decode base64 that may be wrapped in a data URI or a PEM block,
+/// returning the wrapper that was stripped.
+pub fn decode_armored(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+    padding: Base64Padding,
+) -> anyhow::Result<Base64Armor> {
+    let mut head = vec![0u8; 64];
+    let len = read_full(reader, &mut head)?;
+    head.truncate(len);
+    let start = head
+        .iter()
+        .position(|c| !c.is_ascii_whitespace())
+        .unwrap_or(len);
+    let head = head.split_off(start);
+    let is_data_uri = head.starts_with(b"data:");
+    let is_pem = head.starts_with(b"-----BEGIN ");
+    let mut reader = BufReader::new(io::Cursor::new(head).chain(reader));
+
+    if is_data_uri {
+        let mut header = Vec::new();
+        (&mut reader)
+            .take(MAX_ARMOR_LINE)
+            .read_until(b',', &mut header)?;
+        let header = String::from_utf8(header)?;
+        let media_type = header
+            .strip_prefix("data:")
+            .and_then(|h| h.strip_suffix(','))
+            .ok_or_else(|| anyhow::anyhow!("Invalid data URI header"))?;
+        let mime = media_type
+            .strip_suffix(";base64")
+            .ok_or_else(|| anyhow::anyhow!("Only base64 data URIs are supported"))?;
+        decode_stream(&mut reader, writer, format, padding)?;
+        let mime = (!mime.is_empty()).then(|| mime.to_string());
+        return Ok(Base64Armor::DataUri(mime));
+    }
+
+    if is_pem {
+        let mut header = String::new();
+        (&mut reader).take(MAX_ARMOR_LINE).read_line(&mut header)?;
+        let label = header
+            .trim_end()
+            .strip_prefix("-----BEGIN ")
+            .and_then(|h| h.strip_suffix("-----"))
+            .ok_or_else(|| anyhow::anyhow!("Invalid PEM header"))?
+            .to_string();
+        decode_stream(&mut PemBody::new(&mut reader), writer, format, padding)?;
+        let mut footer = String::new();
+        (&mut reader)
+            .take(MAX_ARMOR_LINE)
+            .read_to_string(&mut footer)?;
+        if !footer.starts_with(&format!("-----END {}-----", label)) {
+            anyhow::bail!("Invalid PEM footer, expected END {}", label);
+        }
+        return Ok(Base64Armor::Pem(label));
+    }
+
+    decode_stream(&mut reader, writer, format, padding)?;
+    Ok(Base64Armor::None)
+}
+
+fn sniff_mime(head: &[u8]) -> &str {
+    if let Some(kind) = infer::get(head) {
+        return kind.mime_type();
+    }
+    match std::str::from_utf8(head) {
+        std::result::Result::Ok(_) => "text/plain",
+        // a multi-byte character cut off at the end of the sniffed bytes
+        Err(e) if e.error_len().is_none() => "text/plain",
+        Err(_) => "application/octet-stream",
+    }
 }
 
-pub fn process_decode(input: &str, format: Base64Format) -> anyhow::Result<Vec<u8>> {
-    let mut reader: Box<dyn Read> = get_reader(input)?;
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
-    // avoid trailing newline
-    let buf = buf.trim_end();
+// RFC 7468: printable ASCII, no hyphen at either end
+fn verify_pem_label(label: &str) -> anyhow::Result<()> {
+    let valid = !label.is_empty()
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c == ' ' || c.is_ascii_graphic());
+    if !valid {
+        anyhow::bail!("Invalid PEM label: {:?}", label);
+    }
+    Ok(())
+}
 
-    let decoded = match format {
-        Base64Format::Standard => STANDARD.decode(buf)?,
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(buf)?,
+/// Encode everything from `reader` into `writer`, chunk by chunk.
+/// Padding defaults to padded for standard and unpadded for urlsafe.
+pub fn encode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+    padding: Option<Base64Padding>,
+    wrap: Option<usize>,
+) -> anyhow::Result<u64> {
+    let padding = padding.unwrap_or(match format {
+        Base64Format::UrlSafe => Base64Padding::Unpadded,
+        _ => Base64Padding::Padded,
+    });
+    let pad = match padding {
+        Base64Padding::Padded => true,
+        Base64Padding::Unpadded => false,
+        Base64Padding::Indifferent => {
+            anyhow::bail!("Padding must be padded or unpadded when encoding")
+        }
+    };
+    let alphabet = match format {
+        Base64Format::Standard => &alphabet::STANDARD,
+        Base64Format::UrlSafe => &alphabet::URL_SAFE,
+        Base64Format::Auto => anyhow::bail!("Auto format is only supported when decoding"),
     };
-    Ok(decoded)
+    let engine = GeneralPurpose::new(
+        alphabet,
+        GeneralPurposeConfig::new().with_encode_padding(pad),
+    );
+
+    let n = match wrap {
+        Some(0) => anyhow::bail!("Wrap column must be greater than 0"),
+        Some(width) => {
+            let mut wrapper = LineWrap::new(writer, width);
+            let mut encoder = EncoderWriter::new(&mut wrapper, &engine);
+            let n = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            n
+        }
+        None => {
+            let mut encoder = EncoderWriter::new(writer, &engine);
+            let n = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            n
+        }
+    };
+    Ok(n)
+}
+
+/// Decode everything from `reader` into `writer`, chunk by chunk.
+/// ASCII whitespace (line breaks from wrapped input, the trailing newline
+/// from stdin) is skipped. With `Base64Format::Auto` either alphabet is
+/// accepted, but not both in one input, and the one seen is logged.
+pub fn decode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+    padding: Base64Padding,
+) -> anyhow::Result<u64> {
+    let alphabet = match format {
+        Base64Format::UrlSafe => &alphabet::URL_SAFE,
+        // auto input is normalized to the standard alphabet
+        Base64Format::Standard | Base64Format::Auto => &alphabet::STANDARD,
+    };
+    let mode = match padding {
+        Base64Padding::Padded => DecodePaddingMode::RequireCanonical,
+        Base64Padding::Unpadded => DecodePaddingMode::RequireNone,
+        Base64Padding::Indifferent => DecodePaddingMode::Indifferent,
+    };
+    let engine = GeneralPurpose::new(
+        alphabet,
+        GeneralPurposeConfig::new().with_decode_padding_mode(mode),
+    );
+
+    // in auto mode the urlsafe alphabet is mapped onto the standard one,
+    // recording which variant was seen. Mixing both is an error.
+    let auto = format == Base64Format::Auto;
+    let (mut standard, mut urlsafe, mut padded) = (false, false, false);
+    let mut filter = SkipWhitespace::new(reader, |c| {
+        let c = match c {
+            b'-' if auto => {
+                urlsafe = true;
+                b'+'
+            }
+            b'_' if auto => {
+                urlsafe = true;
+                b'/'
+            }
+            b'+' | b'/' => {
+                standard = true;
+                c
+            }
+            b'=' => {
+                padded = true;
+                c
+            }
+            c => c,
+        };
+        if standard && urlsafe {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Input mixes the standard and urlsafe base64 alphabets",
+            ));
+        }
+        io::Result::Ok(c)
+    });
+    let n = io::copy(&mut DecoderReader::new(&mut filter, &engine), writer)?;
+    if auto {
+        let format = match urlsafe {
+            true => Base64Format::UrlSafe,
+            false => Base64Format::Standard,
+        };
+        let padding = match padded {
+            true => Base64Padding::Padded,
+            false => Base64Padding::Unpadded,
+        };
+        info!("Detected base64 format: {}, {}", format, padding);
+    }
+    Ok(n)
+}
+
+/// Reads a PEM body, stopping at the dashes of the END line.
+struct PemBody<R> {
+    inner: R,
+    done: bool,
+}
+
+impl<R: BufRead> PemBody<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, done: false }
+    }
+}
+
+impl<R: BufRead> Read for PemBody<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done {
+            return io::Result::Ok(0);
+        }
+        let available = self.inner.fill_buf()?;
+        let (len, done) = match available.iter().position(|&c| c == b'-') {
+            Some(pos) if pos <= buf.len() => (pos, true),
+            _ => (available.len().min(buf.len()), false),
+        };
+        buf[..len].copy_from_slice(&available[..len]);
+        self.inner.consume(len);
+        self.done = done;
+        io::Result::Ok(len)
+    }
+}
+
+/// Inserts a newline every `width` bytes, MIME/PEM style.
+/// No newline is written after the last line.
+struct LineWrap<W> {
+    inner: W,
+    width: usize,
+    column: usize,
+}
+
+impl<W: Write> LineWrap<W> {
+    fn new(inner: W, width: usize) -> Self {
+        Self {
+            inner,
+            width,
+            column: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for LineWrap<W> {
+    // always consumes the whole buffer: EncoderWriter reports a short
+    // write from its delegate as Ok(0), which breaks io::copy
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.width {
+                self.inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+            let len = rest.len().min(self.width - self.column);
+            self.inner.write_all(&rest[..len])?;
+            self.column += len;
+            rest = &rest[len..];
+        }
+        io::Result::Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use base64::{
+        engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+        Engine as _,
+    };
+
+    fn encode(data: &[u8], format: Base64Format, padding: Option<Base64Padding>) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encode_stream(&mut &data[..], &mut encoded, format, padding, None).unwrap();
+        encoded
+    }
+
+    fn decode(
+        data: &[u8],
+        format: Base64Format,
+        padding: Base64Padding,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        decode_stream(&mut &data[..], &mut decoded, format, padding)?;
+        Ok(decoded)
+    }
 
     #[test]
-    fn test_process_encode() {
+    fn test_process_encode() -> anyhow::Result<()> {
         let input = "Cargo.toml";
         let format = Base64Format::Standard;
-        println!("{:?}", process_encode(input, format));
-        assert!(process_encode(input, format).is_ok());
+        let mut encoded = Vec::new();
+        encode_stream(&mut get_reader(input)?, &mut encoded, format, None, None)?;
+        assert_eq!(encoded, STANDARD.encode(std::fs::read(input)?).into_bytes());
+        Ok(())
     }
 
     #[test]
-    fn test_process_decode() {
+    fn test_process_decode() -> anyhow::Result<()> {
         let input = "fixtures/b64.txt";
         let format = Base64Format::Standard;
-        assert!(process_decode(input, format).is_ok());
+        let mut decoded = Vec::new();
+        decode_stream(
+            &mut get_reader(input)?,
+            &mut decoded,
+            format,
+            Base64Padding::Indifferent,
+        )?;
+        let expected = std::fs::read_to_string(input)?;
+        assert_eq!(decoded, STANDARD.decode(expected.trim())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_roundtrip_binary() -> anyhow::Result<()> {
+        // larger than the io::copy buffer and not valid utf-8
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let mut encoded = encode(&data, Base64Format::Standard, None);
+        assert_eq!(encoded, STANDARD.encode(&data).into_bytes());
+        encoded.push(b'\n');
+        assert_eq!(
+            decode(&encoded, Base64Format::Standard, Base64Padding::Padded)?,
+            data
+        );
+
+        let encoded = encode(&data, Base64Format::UrlSafe, None);
+        assert_eq!(encoded, URL_SAFE_NO_PAD.encode(&data).into_bytes());
+        assert_eq!(
+            decode(&encoded, Base64Format::UrlSafe, Base64Padding::Unpadded)?,
+            data
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_padding_matrix() -> anyhow::Result<()> {
+        let data = b"hello!?";
+        assert_eq!(
+            encode(data, Base64Format::Standard, Some(Base64Padding::Unpadded)),
+            b"aGVsbG8hPw"
+        );
+        assert_eq!(
+            encode(data, Base64Format::UrlSafe, Some(Base64Padding::Padded)),
+            b"aGVsbG8hPw=="
+        );
+        assert!(decode(b"aGVsbG8hPw", Base64Format::Standard, Base64Padding::Padded).is_err());
+        assert!(decode(
+            b"aGVsbG8hPw==",
+            Base64Format::Standard,
+            Base64Padding::Unpadded
+        )
+        .is_err());
+        for input in [&b"aGVsbG8hPw"[..], b"aGVsbG8hPw=="] {
+            assert_eq!(
+                decode(input, Base64Format::Standard, Base64Padding::Indifferent)?,
+                data
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_and_lenient_decode() -> anyhow::Result<()> {
+        let data = [0xffu8; 100];
+        let mut encoded = Vec::new();
+        encode_stream(
+            &mut &data[..],
+            &mut encoded,
+            Base64Format::Standard,
+            None,
+            Some(76),
+        )?;
+        let text = String::from_utf8(encoded.clone())?;
+        let lines: Vec<_> = text.split('\n').collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 76);
+        assert!(!text.ends_with('\n'));
+
+        let crlf = text.replace('\n', "\r\n");
+        assert_eq!(
+            decode(
+                crlf.as_bytes(),
+                Base64Format::Standard,
+                Base64Padding::Padded
+            )?,
+            data
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_decode() -> anyhow::Result<()> {
+        let data = [0xfbu8, 0xff, 0xbf, 0x01];
+        for encoded in [
+            STANDARD.encode(data),
+            URL_SAFE_NO_PAD.encode(data),
+            base64::engine::general_purpose::URL_SAFE.encode(data),
+        ] {
+            assert_eq!(
+                decode(
+                    encoded.as_bytes(),
+                    Base64Format::Auto,
+                    Base64Padding::Indifferent
+                )?,
+                data
+            );
+        }
+
+        // an explicit padding is still enforced
+        let unpadded = URL_SAFE_NO_PAD.encode([0xfbu8]);
+        assert!(decode(
+            unpadded.as_bytes(),
+            Base64Format::Auto,
+            Base64Padding::Padded
+        )
+        .is_err());
+        assert!(decode(
+            unpadded.as_bytes(),
+            Base64Format::Auto,
+            Base64Padding::Unpadded
+        )
+        .is_ok());
+
+        // one input can't use both alphabets
+        assert!(decode(b"+-AA", Base64Format::Auto, Base64Padding::Indifferent).is_err());
+        assert!(decode(b"/_AA", Base64Format::Auto, Base64Padding::Indifferent).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_rejects_decode_only_options() {
+        let mut out = Vec::new();
+        assert!(encode_stream(&mut &b""[..], &mut out, Base64Format::Auto, None, None).is_err());
+        assert!(encode_stream(
+            &mut &b""[..],
+            &mut out,
+            Base64Format::Standard,
+            Some(Base64Padding::Indifferent),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_data_uri_roundtrip() -> anyhow::Result<()> {
+        let png = std::fs::read("fixtures/jwt-io-decode.png")?;
+        let mut encoded = Vec::new();
+        encode_armored(&mut &png[..], &mut encoded, &Base64Armor::DataUri(None))?;
+        assert!(encoded.starts_with(b"data:image/png;base64,iVBOR"));
+
+        let mut decoded = Vec::new();
+        let armor = decode_armored(
+            &mut &encoded[..],
+            &mut decoded,
+            Base64Format::Standard,
+            Base64Padding::Padded,
+        )?;
+        assert_eq!(armor, Base64Armor::DataUri(Some("image/png".into())));
+        assert_eq!(decoded, png);
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_uri_mime() -> anyhow::Result<()> {
+        let mut encoded = Vec::new();
+        encode_armored(
+            &mut &b"hello"[..],
+            &mut encoded,
+            &Base64Armor::DataUri(None),
+        )?;
+        assert_eq!(encoded, b"data:text/plain;base64,aGVsbG8=");
+
+        let mut encoded = Vec::new();
+        let armor = Base64Armor::DataUri(Some("text/html".into()));
+        encode_armored(&mut &b"<p>"[..], &mut encoded, &armor)?;
+        assert_eq!(encoded, b"data:text/html;base64,PHA+");
+
+        let mut decoded = Vec::new();
+        let ret = decode_armored(
+            &mut &b"data:text/plain,hello"[..],
+            &mut decoded,
+            Base64Format::Standard,
+            Base64Padding::Padded,
+        );
+        assert!(ret.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pem_roundtrip() -> anyhow::Result<()> {
+        let data = [0x42u8; 100];
+        let armor = Base64Armor::Pem("TEST KEY".into());
+        let mut encoded = Vec::new();
+        encode_armored(&mut &data[..], &mut encoded, &armor)?;
+        let text = String::from_utf8(encoded.clone())?;
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "-----BEGIN TEST KEY-----");
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[4], "-----END TEST KEY-----");
+
+        let mut decoded = Vec::new();
+        let ret = decode_armored(
+            &mut &encoded[..],
+            &mut decoded,
+            Base64Format::Standard,
+            Base64Padding::Padded,
+        )?;
+        assert_eq!(ret, armor);
+        assert_eq!(decoded, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pem_invalid() {
+        let mut out = Vec::new();
+        let armor = Base64Armor::Pem("-BAD".into());
+        assert!(encode_armored(&mut &b""[..], &mut out, &armor).is_err());
+
+        let pem = b"-----BEGIN A-----\naGVsbG8=\n-----END B-----\n";
+        let ret = decode_armored(
+            &mut &pem[..],
+            &mut out,
+            Base64Format::Standard,
+            Base64Padding::Padded,
+        );
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_decode_invalid_input() {
+        assert!(decode(b"@@@@", Base64Format::Standard, Base64Padding::Indifferent).is_err());
     }
 }
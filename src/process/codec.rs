@@ -0,0 +1,427 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    get_reader, get_writer, read_full, Base32Format, Base58Format, Base85Format, HexFormat,
+    SkipWhitespace,
+};
+use anyhow::{Ok, Result};
+use data_encoding::{BASE32, BASE32HEX, BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE, HEXUPPER};
+
+// number of blocks handled per read, keeps memory constant for large inputs
+const BLOCKS_PER_CHUNK: usize = 1024;
+
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+pub trait Codec {
+    // Number of input bytes that can be encoded independently of the rest,
+    // 0 if the codec needs the whole input at once
+    fn encode_block(&self) -> usize;
+    // Same as `encode_block` but for encoded input
+    fn decode_block(&self) -> usize;
+    // Whether ASCII whitespace in encoded input is insignificant
+    fn skip_whitespace(&self) -> bool {
+        true
+    }
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>>;
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+pub fn process_codec_encode(input: &str, output: &str, codec: impl Codec) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    codec_encode_stream(&mut reader, &mut writer, &codec)?;
+    // keep the terminal prompt on its own line
+    if output == "-" {
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn process_codec_decode(input: &str, output: &str, codec: impl Codec) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    codec_decode_stream(&mut reader, &mut writer, &codec)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn codec_encode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    codec: &impl Codec,
+) -> Result<()> {
+    copy_chunks(reader, writer, codec.encode_block(), |chunk| {
+        codec.encode(chunk)
+    })
+}
+
+/// ASCII whitespace in the encoded input is skipped unless the codec says otherwise.
+pub fn codec_decode_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    codec: &impl Codec,
+) -> Result<()> {
+    let decode = |chunk: &[u8]| codec.decode(chunk);
+    if codec.skip_whitespace() {
+        let mut reader = SkipWhitespace::new(reader, io::Result::Ok);
+        copy_chunks(&mut reader, writer, codec.decode_block(), decode)
+    } else {
+        copy_chunks(reader, writer, codec.decode_block(), decode)
+    }
+}
+
+fn copy_chunks(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    block: usize,
+    f: impl Fn(&[u8]) -> Result<Vec<u8>>,
+) -> Result<()> {
+    if block == 0 {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        writer.write_all(&f(&buf)?)?;
+        return Ok(());
+    }
+
+    let mut buf = vec![0u8; block * BLOCKS_PER_CHUNK];
+    loop {
+        let n = read_full(reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&f(&buf[..n])?)?;
+        if n < buf.len() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Codec for Base32Format {
+    fn encode_block(&self) -> usize {
+        5
+    }
+
+    fn decode_block(&self) -> usize {
+        8
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let encoding = match self {
+            Base32Format::Standard => &BASE32,
+            Base32Format::NoPad => &BASE32_NOPAD,
+            Base32Format::Hex => &BASE32HEX,
+        };
+        Ok(encoding.encode(data).into_bytes())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        // TOTP secrets are often shared in lower case
+        let data = data.to_ascii_uppercase();
+        let encoding = match self {
+            Base32Format::Standard => &BASE32,
+            Base32Format::NoPad => &BASE32_NOPAD,
+            Base32Format::Hex => &BASE32HEX,
+        };
+        Ok(encoding.decode(&data)?)
+    }
+}
+
+impl Codec for Base58Format {
+    fn encode_block(&self) -> usize {
+        0
+    }
+
+    fn decode_block(&self) -> usize {
+        0
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(bs58::encode(data).with_alphabet(self.alphabet()).into_vec())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(bs58::decode(data)
+            .with_alphabet(self.alphabet())
+            .into_vec()?)
+    }
+}
+
+impl Base58Format {
+    fn alphabet(&self) -> &'static bs58::Alphabet {
+        match self {
+            Base58Format::Bitcoin => bs58::Alphabet::BITCOIN,
+            Base58Format::Ripple => bs58::Alphabet::RIPPLE,
+            Base58Format::Flickr => bs58::Alphabet::FLICKR,
+        }
+    }
+}
+
+impl Codec for Base85Format {
+    fn encode_block(&self) -> usize {
+        4
+    }
+
+    fn decode_block(&self) -> usize {
+        match self {
+            // `z` stands for a whole group, so blocks can't be found without decoding
+            Base85Format::Ascii85 => 0,
+            Base85Format::Z85 => 5,
+        }
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Base85Format::Ascii85 => Ok(ascii85_encode(data)),
+            Base85Format::Z85 => z85_encode(data),
+        }
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Base85Format::Ascii85 => ascii85_decode(data),
+            Base85Format::Z85 => z85_decode(data),
+        }
+    }
+}
+
+impl Codec for HexFormat {
+    fn encode_block(&self) -> usize {
+        1
+    }
+
+    fn decode_block(&self) -> usize {
+        2
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let encoded = match self {
+            HexFormat::Lower => HEXLOWER.encode(data),
+            HexFormat::Upper => HEXUPPER.encode(data),
+        };
+        Ok(encoded.into_bytes())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(HEXLOWER_PERMISSIVE.decode(data)?)
+    }
+}
+
+fn base85_digits(value: u32) -> [u8; 5] {
+    let mut digits = [0u8; 5];
+    let mut value = value;
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 85) as u8;
+        value /= 85;
+    }
+    digits
+}
+
+fn base85_value(digits: &[u8]) -> Result<u32> {
+    let value = digits
+        .iter()
+        .fold(0u64, |acc, &digit| acc * 85 + digit as u64);
+    u32::try_from(value).map_err(|_| anyhow::anyhow!("Invalid base85 group"))
+}
+
+fn ascii85_encode(data: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(data.len() / 4 * 5 + 5);
+    for group in data.chunks(4) {
+        let mut bytes = [0u8; 4];
+        bytes[..group.len()].copy_from_slice(group);
+        let value = u32::from_be_bytes(bytes);
+        if value == 0 && group.len() == 4 {
+            ret.push(b'z');
+            continue;
+        }
+        let digits = base85_digits(value);
+        ret.extend(digits[..group.len() + 1].iter().map(|d| d + b'!'));
+    }
+    ret
+}
+
+fn ascii85_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let data = data.strip_prefix(b"<~").unwrap_or(data);
+    let data = data.strip_suffix(b"~>").unwrap_or(data);
+
+    let mut ret = Vec::with_capacity(data.len() / 5 * 4 + 4);
+    let mut group = Vec::with_capacity(5);
+    for &c in data {
+        match c {
+            b'z' if group.is_empty() => ret.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group.push(c - b'!');
+                if group.len() == 5 {
+                    ret.extend_from_slice(&base85_value(&group)?.to_be_bytes());
+                    group.clear();
+                }
+            }
+            _ => anyhow::bail!("Invalid ascii85 character: {:?}", c as char),
+        }
+    }
+    match group.len() {
+        0 => {}
+        1 => anyhow::bail!("Invalid ascii85 length"),
+        n => {
+            // pad the last group with the highest digit, then drop the padding bytes
+            group.resize(5, 84);
+            ret.extend_from_slice(&base85_value(&group)?.to_be_bytes()[..n - 1]);
+        }
+    }
+    Ok(ret)
+}
+
+fn z85_encode(data: &[u8]) -> Result<Vec<u8>> {
+    if !data.len().is_multiple_of(4) {
+        anyhow::bail!("Z85 input length must be a multiple of 4");
+    }
+    let mut ret = Vec::with_capacity(data.len() / 4 * 5);
+    for group in data.chunks(4) {
+        let value = u32::from_be_bytes(group.try_into()?);
+        ret.extend(
+            base85_digits(value)
+                .iter()
+                .map(|&d| Z85_ALPHABET[d as usize]),
+        );
+    }
+    Ok(ret)
+}
+
+fn z85_decode(data: &[u8]) -> Result<Vec<u8>> {
+    if !data.len().is_multiple_of(5) {
+        anyhow::bail!("Z85 input length must be a multiple of 5");
+    }
+    let mut ret = Vec::with_capacity(data.len() / 5 * 4);
+    for group in data.chunks(5) {
+        let digits = group
+            .iter()
+            .map(|c| {
+                Z85_ALPHABET
+                    .iter()
+                    .position(|a| a == c)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid z85 character: {:?}", *c as char))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ret.extend_from_slice(&base85_value(&digits)?.to_be_bytes());
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(codec: impl Codec, data: &[u8]) -> Result<Vec<u8>> {
+        let mut encoded = Vec::new();
+        codec_encode_stream(&mut &data[..], &mut encoded, &codec)?;
+        let mut decoded = Vec::new();
+        codec_decode_stream(&mut &encoded[..], &mut decoded, &codec)?;
+        assert_eq!(decoded, data, "{}", String::from_utf8_lossy(&encoded));
+        Ok(encoded)
+    }
+
+    fn binary(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 256) as u8).collect()
+    }
+
+    #[test]
+    fn test_base32_roundtrip() -> Result<()> {
+        assert_eq!(
+            roundtrip(Base32Format::Standard, b"foobar")?,
+            b"MZXW6YTBOI======"
+        );
+        assert_eq!(roundtrip(Base32Format::NoPad, b"foobar")?, b"MZXW6YTBOI");
+        assert_eq!(
+            roundtrip(Base32Format::Hex, b"foobar")?,
+            b"CPNMUOJ1E8======"
+        );
+        for codec in [
+            Base32Format::Standard,
+            Base32Format::NoPad,
+            Base32Format::Hex,
+        ] {
+            roundtrip(codec, &binary(20_003))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_base32_decode_lenient() -> Result<()> {
+        let mut decoded = Vec::new();
+        codec_decode_stream(
+            &mut &b"mzxw 6ytb oi\n"[..],
+            &mut decoded,
+            &Base32Format::NoPad,
+        )?;
+        assert_eq!(decoded, b"foobar");
+        Ok(())
+    }
+
+    #[test]
+    fn test_base58_roundtrip() -> Result<()> {
+        assert_eq!(
+            roundtrip(Base58Format::Bitcoin, b"hello world")?,
+            b"StV1DL6CwTryKyV"
+        );
+        for codec in [
+            Base58Format::Bitcoin,
+            Base58Format::Ripple,
+            Base58Format::Flickr,
+        ] {
+            roundtrip(codec, &[0, 0, 1, 2, 3, 255])?;
+            roundtrip(codec, &binary(300))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_base85_roundtrip() -> Result<()> {
+        assert_eq!(roundtrip(Base85Format::Ascii85, b"Man ")?, b"9jqo^");
+        assert_eq!(roundtrip(Base85Format::Ascii85, &[0; 8])?, b"zz");
+        assert_eq!(
+            roundtrip(
+                Base85Format::Z85,
+                &[0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B]
+            )?,
+            b"HelloWorld"
+        );
+        for len in 0..8 {
+            roundtrip(Base85Format::Ascii85, &binary(len))?;
+        }
+        roundtrip(Base85Format::Ascii85, &binary(10_001))?;
+        roundtrip(Base85Format::Z85, &binary(10_000))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_base85_invalid() {
+        assert!(Base85Format::Z85.encode(b"abc").is_err());
+        assert!(Base85Format::Z85.decode(b"Hell").is_err());
+        assert!(Base85Format::Ascii85.decode(b"9jqo^~").is_err());
+        assert!(Base85Format::Ascii85.decode(b"uuuuu").is_err());
+    }
+
+    #[test]
+    fn test_ascii85_delimiters() -> Result<()> {
+        assert_eq!(Base85Format::Ascii85.decode(b"<~9jqo^~>")?, b"Man ");
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_roundtrip() -> Result<()> {
+        assert_eq!(
+            roundtrip(HexFormat::Lower, &[0xde, 0xad, 0xbe, 0xef])?,
+            b"deadbeef"
+        );
+        assert_eq!(
+            roundtrip(HexFormat::Upper, &[0xde, 0xad, 0xbe, 0xef])?,
+            b"DEADBEEF"
+        );
+        roundtrip(HexFormat::Lower, &binary(5_000))?;
+        assert!(HexFormat::Lower.decode(b"abc").is_err());
+        Ok(())
+    }
+}
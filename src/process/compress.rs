@@ -0,0 +1,279 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{get_reader, get_writer, CompressionFormat};
+use anyhow::{Ok, Result};
+use flate2::{
+    read::{MultiGzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use tracing::info;
+
+pub fn process_compress(
+    input: &str,
+    output: &str,
+    format: CompressionFormat,
+    level: Option<u32>,
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    compress_stream(&mut reader, &mut writer, format, level)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn process_decompress(input: &str, output: &str, format: CompressionFormat) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    decompress_stream(&mut reader, &mut writer, format)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write a `<file>.<ext>` next to every file under `dir` for each format, the
+/// layout `ServeDir::precompressed_*` looks for. Existing compressed variants
+/// are not compressed again. Returns the files written.
+pub fn process_precompress(
+    dir: &Path,
+    formats: &[CompressionFormat],
+    level: Option<u32>,
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+
+    let mut ret = Vec::new();
+    for file in files {
+        let compressed = file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| CompressionFormat::from_extension(ext).is_some());
+        if compressed {
+            continue;
+        }
+        for &format in formats {
+            let mut name = file.clone().into_os_string();
+            name.push(".");
+            name.push(format.extension());
+            let target = PathBuf::from(name);
+
+            let mut reader = File::open(&file)?;
+            let mut writer = BufWriter::new(File::create(&target)?);
+            compress_stream(&mut reader, &mut writer, format, level)?;
+            writer.flush()?;
+            info!("Compressed {:?} to {:?}", file, target);
+            ret.push(target);
+        }
+    }
+    Ok(ret)
+}
+
+// symlinks are skipped: they can loop or point outside of `dir`
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Compress everything from `reader` into `writer` in constant memory.
+/// Without a level each format uses its own default. Returns the number of
+/// uncompressed bytes read.
+pub fn compress_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: CompressionFormat,
+    level: Option<u32>,
+) -> Result<u64> {
+    let level = level.unwrap_or(format.default_level());
+    let (min, max) = format.level_range();
+    if level < min || level > max {
+        anyhow::bail!(
+            "Invalid {} level {}, expected {}..={}",
+            format,
+            level,
+            min,
+            max
+        );
+    }
+
+    let n = match format {
+        CompressionFormat::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::new(level));
+            let n = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            n
+        }
+        CompressionFormat::Deflate => {
+            let mut encoder = ZlibEncoder::new(writer, Compression::new(level));
+            let n = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            n
+        }
+        CompressionFormat::Brotli => {
+            let params = brotli::enc::BrotliEncoderParams {
+                quality: level as i32,
+                ..Default::default()
+            };
+            // BrotliCompress returns the bytes written, count the input instead
+            let mut input = reader.take(u64::MAX);
+            brotli::BrotliCompress(&mut input, &mut &mut *writer, &params)?;
+            u64::MAX - input.limit()
+        }
+        CompressionFormat::Zstd => {
+            let mut encoder = zstd::Encoder::new(writer, level as i32)?;
+            let n = io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+            n
+        }
+    };
+    Ok(n)
+}
+
+/// Decompress everything from `reader` into `writer` in constant memory.
+/// Returns the number of uncompressed bytes written.
+pub fn decompress_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: CompressionFormat,
+) -> Result<u64> {
+    let n = match format {
+        CompressionFormat::Gzip => io::copy(&mut MultiGzDecoder::new(reader), writer)?,
+        CompressionFormat::Deflate => io::copy(&mut ZlibDecoder::new(reader), writer)?,
+        CompressionFormat::Brotli => {
+            io::copy(&mut brotli::Decompressor::new(reader, 4096), writer)?
+        }
+        CompressionFormat::Zstd => io::copy(&mut zstd::Decoder::new(reader)?, writer)?,
+    };
+    Ok(n)
+}
+
+impl CompressionFormat {
+    // extension used by tower-http's ServeDir for precompressed files
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CompressionFormat::Gzip => "gz",
+            CompressionFormat::Deflate => "zz",
+            CompressionFormat::Brotli => "br",
+            CompressionFormat::Zstd => "zst",
+        }
+    }
+
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "gz" => Some(CompressionFormat::Gzip),
+            "zz" => Some(CompressionFormat::Deflate),
+            "br" => Some(CompressionFormat::Brotli),
+            "zst" => Some(CompressionFormat::Zstd),
+            _ => None,
+        }
+    }
+
+    fn default_level(&self) -> u32 {
+        match self {
+            CompressionFormat::Gzip | CompressionFormat::Deflate => 6,
+            CompressionFormat::Brotli => 11,
+            CompressionFormat::Zstd => 3,
+        }
+    }
+
+    fn level_range(&self) -> (u32, u32) {
+        match self {
+            CompressionFormat::Gzip | CompressionFormat::Deflate => (0, 9),
+            CompressionFormat::Brotli => (0, 11),
+            CompressionFormat::Zstd => (1, 22),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: [CompressionFormat; 4] = [
+        CompressionFormat::Gzip,
+        CompressionFormat::Deflate,
+        CompressionFormat::Brotli,
+        CompressionFormat::Zstd,
+    ];
+
+    #[test]
+    fn test_compress_roundtrip() -> Result<()> {
+        let data = fs::read("assets/juventus.csv")?;
+        for format in FORMATS {
+            for level in [
+                None,
+                Some(format.level_range().0),
+                Some(format.level_range().1),
+            ] {
+                let mut compressed = Vec::new();
+                let n = compress_stream(&mut &data[..], &mut compressed, format, level)?;
+                assert_eq!(n, data.len() as u64, "{}", format);
+                if level.is_none() {
+                    assert!(compressed.len() < data.len(), "{}", format);
+                }
+
+                let mut decompressed = Vec::new();
+                let n = decompress_stream(&mut &compressed[..], &mut decompressed, format)?;
+                assert_eq!(n, data.len() as u64, "{}", format);
+                assert_eq!(decompressed, data);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_invalid() {
+        let mut out = Vec::new();
+        assert!(
+            compress_stream(&mut &b""[..], &mut out, CompressionFormat::Gzip, Some(10)).is_err()
+        );
+        assert!(
+            compress_stream(&mut &b""[..], &mut out, CompressionFormat::Zstd, Some(0)).is_err()
+        );
+        for format in FORMATS {
+            let ret = decompress_stream(&mut &b"not compressed at all"[..], &mut out, format);
+            assert!(ret.is_err(), "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_precompress() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("sub"))?;
+        fs::copy("fixtures/index.html", dir.join("index.html"))?;
+        fs::copy("assets/juventus.csv", dir.join("sub/juventus.csv"))?;
+        // a link back to the root and one to a directory outside the tree
+        let outside = tempfile::tempdir()?;
+        fs::write(outside.path().join("outside.txt"), "outside")?;
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir, dir.join("sub/loop"))?;
+            std::os::unix::fs::symlink(outside.path(), dir.join("outside"))?;
+        }
+
+        let written = process_precompress(dir, &FORMATS, None)?;
+        assert_eq!(written.len(), 8);
+        assert_eq!(fs::read_dir(outside.path())?.count(), 1);
+        let gz = fs::read(dir.join("sub/juventus.csv.gz"))?;
+        let mut csv = Vec::new();
+        decompress_stream(&mut &gz[..], &mut csv, CompressionFormat::Gzip)?;
+        assert_eq!(csv, fs::read("assets/juventus.csv")?);
+
+        // running again doesn't compress the compressed files
+        let written = process_precompress(dir, &[CompressionFormat::Gzip], None)?;
+        assert_eq!(written.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use std::io::Read;
+
+use crate::{read_full, Argon2Cost, Argon2Kdf, TextEncryptFormat};
+use anyhow::{Ok, Result};
+
+/// Binary envelope in front of every ciphertext written by `text encrypt`:
+///
+/// ```text
+/// magic      4  "RCLI"
+/// version    1  ENVELOPE_VERSION
+/// cipher     1  TextEncryptFormat id
+/// chunk      4  u32 BE plaintext bytes per STREAM chunk, 0 for a single message
+/// key id     8  fingerprint of the key, see `key_id`
+/// kdf        1  0 for a key file, 1 for an Argon2id passphrase followed by
+///               memory KiB u32 BE, iterations u32 BE, lanes u8, salt length
+///               u8 and the salt
+/// nonce      1  length, then the nonce (the nonce prefix for STREAM)
+/// aad        2  u16 BE length, then associated data stored in the clear
+/// ```
+///
+/// The encoded header is passed as associated data to the cipher, so none
+/// of it can be changed without failing decryption.
+pub const ENVELOPE_MAGIC: &[u8; 4] = b"RCLI";
+pub const ENVELOPE_VERSION: u8 = 1;
+pub const ENVELOPE_PEM_LABEL: &str = "RCLI ENCRYPTED MESSAGE";
+// a private key file encrypted under a passphrase, see `protect_private_key`
+pub const PROTECTED_KEY_PEM_LABEL: &str = "RCLI ENCRYPTED PRIVATE KEY";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub cipher: TextEncryptFormat,
+    pub chunk_size: u32,
+    pub key_id: [u8; 8],
+    pub kdf: Option<Argon2Kdf>,
+    pub nonce: Vec<u8>,
+    pub aad: Vec<u8>,
+}
+
+impl Envelope {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(21 + self.nonce.len() + self.aad.len());
+        buf.extend_from_slice(ENVELOPE_MAGIC);
+        buf.push(ENVELOPE_VERSION);
+        buf.push(self.cipher.id());
+        buf.extend_from_slice(&self.chunk_size.to_be_bytes());
+        buf.extend_from_slice(&self.key_id);
+        match &self.kdf {
+            None => buf.push(0),
+            Some(kdf) => {
+                buf.push(1);
+                buf.extend_from_slice(&kdf.cost.memory_kib.to_be_bytes());
+                buf.extend_from_slice(&kdf.cost.iterations.to_be_bytes());
+                buf.push(kdf.parallelism);
+                buf.push(kdf.salt.len() as u8);
+                buf.extend_from_slice(&kdf.salt);
+            }
+        }
+        buf.push(self.nonce.len() as u8);
+        buf.extend_from_slice(&self.nonce);
+        buf.extend_from_slice(&(self.aad.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&self.aad);
+        buf
+    }
+
+    /// Read a header, magic included, leaving the reader at the ciphertext.
+    pub fn read_from(reader: &mut dyn Read) -> Result<Self> {
+        let fixed: [u8; 19] = read_array(reader)?;
+        if &fixed[..4] != ENVELOPE_MAGIC {
+            anyhow::bail!("Not an rcli encrypted message");
+        }
+        if fixed[4] != ENVELOPE_VERSION {
+            anyhow::bail!("Unsupported envelope version {}", fixed[4]);
+        }
+        let cipher = TextEncryptFormat::from_id(fixed[5])?;
+        let chunk_size = u32::from_be_bytes(fixed[6..10].try_into()?);
+        let key_id = fixed[10..18].try_into()?;
+        let kdf = match fixed[18] {
+            0 => None,
+            1 => {
+                let params: [u8; 10] = read_array(reader)?;
+                let cost = Argon2Cost {
+                    memory_kib: u32::from_be_bytes(params[..4].try_into()?),
+                    iterations: u32::from_be_bytes(params[4..8].try_into()?),
+                };
+                let salt = read_vec(reader, params[9] as usize)?;
+                Some(Argon2Kdf {
+                    cost,
+                    parallelism: params[8],
+                    salt,
+                })
+            }
+            kdf => anyhow::bail!("Unknown key derivation {} in envelope", kdf),
+        };
+
+        let [len] = read_array(reader)?;
+        let nonce = read_vec(reader, len as usize)?;
+        let len: [u8; 2] = read_array(reader)?;
+        let aad = read_vec(reader, u16::from_be_bytes(len) as usize)?;
+
+        Ok(Self {
+            cipher,
+            chunk_size,
+            key_id,
+            kdf,
+            nonce,
+            aad,
+        })
+    }
+}
+
+impl TextEncryptFormat {
+    // wire id in the envelope header, never reuse a retired one
+    pub fn id(self) -> u8 {
+        match self {
+            TextEncryptFormat::ChaCha20Poly1305 => 1,
+            TextEncryptFormat::XChaCha20Poly1305 => 2,
+            TextEncryptFormat::Aes256Gcm => 3,
+            TextEncryptFormat::Aes256GcmSiv => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(TextEncryptFormat::ChaCha20Poly1305),
+            2 => Ok(TextEncryptFormat::XChaCha20Poly1305),
+            3 => Ok(TextEncryptFormat::Aes256Gcm),
+            4 => Ok(TextEncryptFormat::Aes256GcmSiv),
+            _ => Err(anyhow::anyhow!("Unknown cipher id {} in envelope", id)),
+        }
+    }
+
+    pub fn nonce_size(self) -> usize {
+        match self {
+            TextEncryptFormat::XChaCha20Poly1305 => 24,
+            _ => 12,
+        }
+    }
+}
+
+/// Short, non-secret identifier for a symmetric key, used to report a
+/// wrong key before trying to decrypt.
+pub fn key_id(key: &[u8]) -> [u8; 8] {
+    let hash = blake3::derive_key("rcli 2024 text encrypt key id", key);
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&hash[..8]);
+    id
+}
+
+fn read_array<const N: usize>(reader: &mut dyn Read) -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    read_header(reader, &mut buf)?;
+    Ok(buf)
+}
+
+fn read_vec(reader: &mut dyn Read, len: usize) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    read_header(reader, &mut buf)?;
+    Ok(buf)
+}
+
+fn read_header(reader: &mut dyn Read, buf: &mut [u8]) -> Result<()> {
+    if read_full(reader, buf)? < buf.len() {
+        anyhow::bail!("Encrypted message header is truncated");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_roundtrip() -> Result<()> {
+        let envelope = Envelope {
+            cipher: TextEncryptFormat::ChaCha20Poly1305,
+            chunk_size: 65536,
+            key_id: key_id(b"key"),
+            kdf: Some(Argon2Kdf::generate(Argon2Cost::default())),
+            nonce: vec![1; 7],
+            aad: b"tenant=acme".to_vec(),
+        };
+        let mut bytes = envelope.to_bytes();
+        assert!(bytes.starts_with(b"RCLI\x01\x01\x00\x01\x00\x00"));
+        bytes.extend_from_slice(b"ciphertext");
+
+        let mut reader = &bytes[..];
+        assert_eq!(Envelope::read_from(&mut reader)?, envelope);
+        assert_eq!(reader, b"ciphertext");
+        Ok(())
+    }
+
+    #[test]
+    fn test_envelope_rejects_bad_headers() {
+        let bytes = Envelope {
+            cipher: TextEncryptFormat::ChaCha20Poly1305,
+            chunk_size: 0,
+            key_id: [0; 8],
+            kdf: None,
+            nonce: vec![0; 12],
+            aad: Vec::new(),
+        }
+        .to_bytes();
+        assert!(Envelope::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+        assert!(Envelope::read_from(&mut &b"RCLX"[..]).is_err());
+
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert!(Envelope::read_from(&mut &version[..]).is_err());
+        let mut cipher = bytes.clone();
+        cipher[5] = 0xff;
+        assert!(Envelope::read_from(&mut &cipher[..]).is_err());
+        let mut kdf = bytes.clone();
+        kdf[18] = 2;
+        assert!(Envelope::read_from(&mut &kdf[..]).is_err());
+    }
+}
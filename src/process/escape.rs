@@ -0,0 +1,156 @@
+use crate::{Codec, EscapeFormat};
+use anyhow::{Ok, Result};
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+// everything but the RFC 3986 unreserved characters, like encodeURIComponent
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+// additionally keeps the reserved characters that structure a URL, like encodeURI
+const URL_FULL: &AsciiSet = &URL_COMPONENT
+    .remove(b';')
+    .remove(b',')
+    .remove(b'/')
+    .remove(b'?')
+    .remove(b':')
+    .remove(b'@')
+    .remove(b'&')
+    .remove(b'=')
+    .remove(b'+')
+    .remove(b'$')
+    .remove(b'!')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'#');
+
+impl Codec for EscapeFormat {
+    fn encode_block(&self) -> usize {
+        match self {
+            EscapeFormat::UrlComponent | EscapeFormat::Url | EscapeFormat::Form => 1,
+            // multi-byte characters must not be split
+            EscapeFormat::Html | EscapeFormat::Json => 0,
+        }
+    }
+
+    fn decode_block(&self) -> usize {
+        0
+    }
+
+    fn skip_whitespace(&self) -> bool {
+        false
+    }
+
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let encoded = match self {
+            EscapeFormat::UrlComponent => percent_encode(data, URL_COMPONENT).to_string(),
+            EscapeFormat::Url => percent_encode(data, URL_FULL).to_string(),
+            EscapeFormat::Form => form_urlencoded::byte_serialize(data).collect(),
+            EscapeFormat::Html => html_escape::encode_safe(std::str::from_utf8(data)?).into(),
+            EscapeFormat::Json => serde_json::to_string(std::str::from_utf8(data)?)?,
+        };
+        Ok(encoded.into_bytes())
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>> {
+        // avoid trailing newline
+        let data = data.strip_suffix(b"\n").unwrap_or(data);
+        let data = data.strip_suffix(b"\r").unwrap_or(data);
+        let decoded = match self {
+            EscapeFormat::UrlComponent | EscapeFormat::Url => percent_decode(data).collect(),
+            EscapeFormat::Form => {
+                let data: Vec<u8> = data
+                    .iter()
+                    .map(|&c| if c == b'+' { b' ' } else { c })
+                    .collect();
+                percent_decode(&data).collect()
+            }
+            EscapeFormat::Html => html_escape::decode_html_entities(std::str::from_utf8(data)?)
+                .into_owned()
+                .into_bytes(),
+            EscapeFormat::Json => {
+                let text = std::str::from_utf8(data)?;
+                // the surrounding quotes are optional
+                let quoted = if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+                    text.to_string()
+                } else {
+                    format!("\"{}\"", text)
+                };
+                serde_json::from_str::<String>(&quoted)?.into_bytes()
+            }
+        };
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{codec_decode_stream, codec_encode_stream};
+
+    fn roundtrip(format: EscapeFormat, data: &str) -> Result<String> {
+        let mut encoded = Vec::new();
+        codec_encode_stream(&mut data.as_bytes(), &mut encoded, &format)?;
+        let mut decoded = Vec::new();
+        codec_decode_stream(&mut &encoded[..], &mut decoded, &format)?;
+        assert_eq!(String::from_utf8(decoded)?, data);
+        Ok(String::from_utf8(encoded)?)
+    }
+
+    #[test]
+    fn test_url_roundtrip() -> Result<()> {
+        let url = "https://example.com/a b?q=1&r=ü#top";
+        assert_eq!(
+            roundtrip(EscapeFormat::UrlComponent, url)?,
+            "https%3A%2F%2Fexample.com%2Fa%20b%3Fq%3D1%26r%3D%C3%BC%23top"
+        );
+        assert_eq!(
+            roundtrip(EscapeFormat::Url, url)?,
+            "https://example.com/a%20b?q=1&r=%C3%BC#top"
+        );
+        assert_eq!(
+            roundtrip(EscapeFormat::Form, "a b&c=ü")?,
+            "a+b%26c%3D%C3%BC"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_html_roundtrip() -> Result<()> {
+        assert_eq!(
+            roundtrip(EscapeFormat::Html, "<a href=\"x\">Tom & Jerry's</a>")?,
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#x27;s&lt;&#x2F;a&gt;"
+        );
+        assert_eq!(
+            EscapeFormat::Html.decode(b"&copy; &#169; &#xa9;\n")?,
+            "© © ©".as_bytes()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_roundtrip() -> Result<()> {
+        assert_eq!(
+            roundtrip(EscapeFormat::Json, "say \"hi\"\n\ttab\u{1}")?,
+            r#""say \"hi\"\n\ttab\u0001""#
+        );
+        assert_eq!(
+            EscapeFormat::Json.decode(r#"aé\/"#.as_bytes())?,
+            "aé/".as_bytes()
+        );
+        assert!(EscapeFormat::Json.decode(br#"bad\q"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_keeps_spaces() -> Result<()> {
+        let mut decoded = Vec::new();
+        codec_decode_stream(&mut &b"a%20b c\n"[..], &mut decoded, &EscapeFormat::Url)?;
+        assert_eq!(decoded, b"a b c");
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{get_reader, HashEncoding, HashFormat, UpdateWriter};
+use anyhow::{Ok, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
+use sha2::{Digest, Sha256, Sha512};
+use sha3::{Sha3_256, Sha3_512};
+use xxhash_rust::xxh3::Xxh3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashCheck {
+    pub name: String,
+    pub status: HashCheckStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashCheckStatus {
+    Ok,
+    Failed,
+    Unreadable,
+}
+
+impl HashFormat {
+    pub fn digest_size(self) -> usize {
+        match self {
+            HashFormat::Blake3 | HashFormat::Sha256 | HashFormat::Sha3_256 => 32,
+            HashFormat::Sha512 | HashFormat::Sha3_512 => 64,
+            HashFormat::Xxh3 => 8,
+        }
+    }
+}
+
+/// Digest of `input`, a file or - for stdin, in `encoding`
+pub fn process_hash(input: &str, format: HashFormat, encoding: HashEncoding) -> Result<String> {
+    let digest = match input {
+        "-" => hash_reader(&mut get_reader(input)?, format)?,
+        path => hash_file(path, format)?,
+    };
+    let digest = match encoding {
+        HashEncoding::Hex => HEXLOWER.encode(&digest),
+        HashEncoding::Base64 => STANDARD.encode(digest),
+    };
+    Ok(digest)
+}
+
+/// Check every `<digest>  <file>` line of a `sha256sum` style checksum
+/// file. Digests can be hex or base64, files that can't be read are
+/// reported rather than failing the whole check.
+pub fn process_hash_check(input: &str, format: HashFormat) -> Result<Vec<HashCheck>> {
+    let mut content = String::new();
+    get_reader(input)?.read_to_string(&mut content)?;
+
+    let mut checks = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let (digest, name) = parse_checksum_line(line)
+            .and_then(|(digest, name)| Some((decode_digest(digest, format)?, name)))
+            .ok_or_else(|| {
+                anyhow::anyhow!("{}: line {} is not a {} checksum", input, i + 1, format)
+            })?;
+        let status = match fs::metadata(&*name).map(|_| hash_file(&*name, format)) {
+            Result::Ok(Result::Ok(actual)) if actual == digest => HashCheckStatus::Ok,
+            Result::Ok(Result::Ok(_)) => HashCheckStatus::Failed,
+            _ => HashCheckStatus::Unreadable,
+        };
+        checks.push(HashCheck {
+            name: name.into_owned(),
+            status,
+        });
+    }
+    Ok(checks)
+}
+
+/// Split a `<digest>  <file>` line, `*` instead of the second space marks
+/// binary mode in `sha256sum` output. A leading `\` marks a name with a
+/// backslash or newline in it, written as `\\` and `\n` like GNU
+/// coreutils does; any other escape makes the line invalid.
+pub fn parse_checksum_line(line: &str) -> Option<(&str, Cow<'_, str>)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+    let (digest, name) = line.split_once(' ')?;
+    let name = name.strip_prefix(' ').or_else(|| name.strip_prefix('*'))?;
+    if digest.is_empty() || name.is_empty() {
+        return None;
+    }
+    let name = if escaped {
+        Cow::Owned(unescape_checksum_name(name)?)
+    } else {
+        Cow::Borrowed(name)
+    };
+    Some((digest, name))
+}
+
+/// A `<digest>  <file>` line as `sha256sum` writes it, escaping a name with
+/// a backslash or newline in it
+pub fn format_checksum_line(digest: &str, name: &str) -> String {
+    if name.contains(['\\', '\n']) {
+        let name = name.replace('\\', "\\\\").replace('\n', "\\n");
+        format!("\\{}  {}", digest, name)
+    } else {
+        format!("{}  {}", digest, name)
+    }
+}
+
+fn unescape_checksum_name(name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '\\' => unescaped.push('\\'),
+                'n' => unescaped.push('\n'),
+                _ => return None,
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+fn decode_digest(digest: &str, format: HashFormat) -> Option<Vec<u8>> {
+    let size = format.digest_size();
+    let decoded = if digest.len() == size * 2 {
+        HEXLOWER_PERMISSIVE.decode(digest.as_bytes()).ok()?
+    } else {
+        STANDARD.decode(digest).ok()?
+    };
+    (decoded.len() == size).then_some(decoded)
+}
+
+/// Unkeyed digest of everything in `reader`
+pub fn hash_reader(reader: &mut dyn Read, format: HashFormat) -> Result<Vec<u8>> {
+    let digest = match format {
+        HashFormat::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(reader)?;
+            hasher.finalize().as_bytes().to_vec()
+        }
+        HashFormat::Sha256 => digest::<Sha256>(reader)?,
+        HashFormat::Sha512 => digest::<Sha512>(reader)?,
+        HashFormat::Sha3_256 => digest::<Sha3_256>(reader)?,
+        HashFormat::Sha3_512 => digest::<Sha3_512>(reader)?,
+        HashFormat::Xxh3 => {
+            let mut hasher = Xxh3::new();
+            io::copy(reader, &mut UpdateWriter(|data: &[u8]| hasher.update(data)))?;
+            // big endian, as printed by xxhsum
+            hasher.digest().to_be_bytes().to_vec()
+        }
+    };
+    Ok(digest)
+}
+
+// blake3 memory maps large files and hashes them on all cores
+pub fn hash_file(path: impl AsRef<Path>, format: HashFormat) -> Result<Vec<u8>> {
+    match format {
+        HashFormat::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_mmap_rayon(path)?;
+            Ok(hasher.finalize().as_bytes().to_vec())
+        }
+        _ => hash_reader(&mut File::open(path)?, format),
+    }
+}
+
+fn digest<D: Digest + io::Write>(reader: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut hasher = D::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [HashFormat; 6] = [
+        HashFormat::Blake3,
+        HashFormat::Sha256,
+        HashFormat::Sha512,
+        HashFormat::Sha3_256,
+        HashFormat::Sha3_512,
+        HashFormat::Xxh3,
+    ];
+
+    #[test]
+    fn test_hash_reader() -> Result<()> {
+        let vectors = [
+            (
+                HashFormat::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashFormat::Sha512,
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                 2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (
+                HashFormat::Sha3_256,
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            ),
+            (
+                HashFormat::Sha3_512,
+                "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+                 10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+            ),
+            (HashFormat::Xxh3, "78af5f94892f3950"),
+        ];
+        for (format, expected) in vectors {
+            let digest = hash_reader(&mut &b"abc"[..], format)?;
+            assert_eq!(HEXLOWER.encode(&digest), expected, "{}", format);
+        }
+        let blake3 = hash_reader(&mut &b"abc"[..], HashFormat::Blake3)?;
+        assert_eq!(blake3, blake3::hash(b"abc").as_bytes());
+
+        for format in ALL {
+            let expected = hash_reader(&mut &fs::read("Cargo.toml")?[..], format)?;
+            assert_eq!(expected.len(), format.digest_size());
+            assert_eq!(hash_file("Cargo.toml", format)?, expected);
+        }
+        Ok(())
+    }
+
+    // fails its first read with ErrorKind::Interrupted, which must be retried
+    struct Interrupted<'a>(bool, &'a [u8]);
+
+    impl Read for Interrupted<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if !self.0 {
+                self.0 = true;
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            self.1.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_hash_reader_retries_interrupted() -> Result<()> {
+        for format in ALL {
+            let digest = hash_reader(&mut Interrupted(false, b"abc"), format)?;
+            assert_eq!(digest, hash_reader(&mut &b"abc"[..], format)?, "{}", format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hash_check() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("SUMS");
+        for format in ALL {
+            let hex = process_hash("Cargo.toml", format, HashEncoding::Hex)?;
+            let base64 = process_hash("Cargo.toml", format, HashEncoding::Base64)?;
+            let wrong = HEXLOWER.encode(&vec![0; format.digest_size()]);
+            fs::write(
+                &path,
+                format!(
+                    "{}  Cargo.toml\n{} *Cargo.toml\n\n{}  Cargo.toml\n{}  missing.txt\n",
+                    hex, base64, wrong, hex
+                ),
+            )?;
+            let statuses: Vec<_> = process_hash_check(path.to_str().unwrap(), format)?
+                .into_iter()
+                .map(|check| check.status)
+                .collect();
+            assert_eq!(
+                statuses,
+                [
+                    HashCheckStatus::Ok,
+                    HashCheckStatus::Ok,
+                    HashCheckStatus::Failed,
+                    HashCheckStatus::Unreadable
+                ]
+            );
+        }
+
+        // a digest of the wrong length is a format error
+        fs::write(&path, "abcd  Cargo.toml\n")?;
+        assert!(process_hash_check(path.to_str().unwrap(), HashFormat::Sha256).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_checksum_line() {
+        assert_eq!(parse_checksum_line("ab  a b"), Some(("ab", "a b".into())));
+        assert_eq!(parse_checksum_line("ab *bin"), Some(("ab", "bin".into())));
+        assert_eq!(parse_checksum_line("ab bin"), None);
+        assert_eq!(parse_checksum_line("ab"), None);
+    }
+
+    #[test]
+    fn test_parse_checksum_line_escaped() {
+        assert_eq!(
+            parse_checksum_line(r"\ab  a\\b\nc"),
+            Some(("ab", "a\\b\nc".into()))
+        );
+        assert_eq!(parse_checksum_line(r"\ab  a\tb"), None);
+        assert_eq!(parse_checksum_line(r"\ab  a\"), None);
+        // without the leading backslash the name is taken as is
+        assert_eq!(
+            parse_checksum_line(r"ab  a\nb"),
+            Some(("ab", r"a\nb".into()))
+        );
+
+        let line = format_checksum_line("ab", "a\\b\nc");
+        assert_eq!(line, r"\ab  a\\b\nc");
+        assert_eq!(parse_checksum_line(&line), Some(("ab", "a\\b\nc".into())));
+        assert_eq!(format_checksum_line("ab", "a b"), "ab  a b");
+    }
+}
@@ -0,0 +1,171 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::{get_reader, get_writer, read_full};
+use anyhow::{Ok, Result};
+use data_encoding::HEXLOWER_PERMISSIVE;
+
+pub fn process_hexdump(
+    input: &str,
+    output: &str,
+    seek: u64,
+    length: Option<u64>,
+    cols: usize,
+    group: usize,
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    hexdump_stream(&mut reader, &mut writer, seek, length, cols, group)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn process_hexdump_reverse(input: &str, output: &str) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut writer = get_writer(output)?;
+    hexdump_reverse_stream(&mut reader, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write an xxd style dump: offset, `cols` bytes per line in groups of
+/// `group` bytes (0 for no spaces) and the printable ASCII column.
+/// `seek` bytes are skipped first and offsets start from there.
+pub fn hexdump_stream(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    seek: u64,
+    length: Option<u64>,
+    cols: usize,
+    group: usize,
+) -> Result<()> {
+    if cols == 0 || cols > 256 {
+        anyhow::bail!("Columns must be between 1 and 256");
+    }
+    let skipped = io::copy(&mut reader.take(seek), &mut io::sink())?;
+    if skipped < seek {
+        anyhow::bail!("Seek offset {} is past the end of the input", seek);
+    }
+    let mut reader = reader.take(length.unwrap_or(u64::MAX));
+
+    // width of the hex column of a full line, so short lines can be padded
+    let hex_width = match group {
+        0 => cols * 2,
+        _ => cols * 2 + cols.div_ceil(group) - 1,
+    };
+    let mut offset = seek;
+    let mut buf = vec![0u8; cols];
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let mut hex = String::with_capacity(hex_width);
+        for (i, b) in buf[..n].iter().enumerate() {
+            if group != 0 && i != 0 && i % group == 0 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x}", b));
+        }
+        let ascii: String = buf[..n]
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(
+            writer,
+            "{:08x}: {:<width$}  {}",
+            offset,
+            hex,
+            ascii,
+            width = hex_width
+        )?;
+        offset += n as u64;
+        if n < cols {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Turn a dump back into binary. Lines with an `offset:` prefix are read
+/// as `hexdump_stream` output (the ASCII column is ignored), other lines
+/// as plain hex. Offsets are not used to reposition the output.
+pub fn hexdump_reverse_stream(reader: &mut dyn Read, writer: &mut dyn Write) -> Result<()> {
+    let reader = BufReader::new(reader);
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let hex = match line.split_once(':') {
+            // the ASCII column starts after the first double space
+            Some((_, rest)) => rest.trim_start().split("  ").next().unwrap_or_default(),
+            None => line.as_str(),
+        };
+        let hex: String = hex.split_whitespace().collect();
+        let bytes = HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Invalid hexdump at line {}: {}", n + 1, e))?;
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump(data: &[u8], seek: u64, length: Option<u64>, cols: usize, group: usize) -> String {
+        let mut out = Vec::new();
+        hexdump_stream(&mut &data[..], &mut out, seek, length, cols, group).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_hexdump_matches_xxd() -> Result<()> {
+        let key = std::fs::read("fixtures/blake3.txt")?;
+        assert_eq!(
+            dump(&key, 0, None, 16, 2),
+            "00000000: 4041 3546 7135 6568 3973 3973 7536 6a6a  @A5Fq5eh9s9su6jj\n\
+             00000010: 796d 406a 5531 4871 4e47 5556 4868 3844  ym@jU1HqNGUVHh8D\n\
+             00000020: 0a                                       .\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexdump_seek_length_group() {
+        let data: Vec<u8> = (0..32).collect();
+        assert_eq!(
+            dump(&data, 4, Some(6), 4, 1),
+            "00000004: 04 05 06 07  ....\n00000008: 08 09        ..\n"
+        );
+        assert_eq!(
+            dump(&data, 30, None, 8, 0),
+            "0000001e: 1e1f              ..\n"
+        );
+        assert_eq!(dump(&data, 32, None, 8, 0), "");
+        let mut out = Vec::new();
+        assert!(hexdump_stream(&mut &data[..], &mut out, 33, None, 16, 2).is_err());
+        assert!(hexdump_stream(&mut &data[..], &mut out, 0, None, 0, 2).is_err());
+    }
+
+    #[test]
+    fn test_hexdump_reverse() -> Result<()> {
+        let data: Vec<u8> = (0..=255).rev().collect();
+        for (cols, group) in [(16, 2), (7, 3), (32, 0)] {
+            let text = dump(&data, 0, None, cols, group);
+            let mut out = Vec::new();
+            hexdump_reverse_stream(&mut text.as_bytes(), &mut out)?;
+            assert_eq!(out, data);
+        }
+
+        let mut out = Vec::new();
+        hexdump_reverse_stream(&mut &b"dead\nBEEF\n"[..], &mut out)?;
+        assert_eq!(out, [0xde, 0xad, 0xbe, 0xef]);
+        assert!(hexdump_reverse_stream(&mut &b"00000000: 0g"[..], &mut out).is_err());
+        Ok(())
+    }
+}
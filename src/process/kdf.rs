@@ -0,0 +1,139 @@
+use std::fs;
+
+use crate::PassphraseSource;
+use anyhow::{Ok, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::{rngs::OsRng, RngCore};
+
+const SALT_SIZE: usize = 16;
+// bounds for parameters read from a header, so a crafted file can't make
+// us allocate unbounded memory or spin forever
+const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+const MAX_ITERATIONS: u32 = 1024;
+
+/// Argon2id work factors, memory in KiB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Cost {
+    pub memory_kib: u32,
+    pub iterations: u32,
+}
+
+impl Default for Argon2Cost {
+    // RFC 9106 second recommended option, with a single lane
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+        }
+    }
+}
+
+/// Argon2id parameters and salt, stored next to whatever they protect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Argon2Kdf {
+    pub cost: Argon2Cost,
+    pub parallelism: u8,
+    pub salt: Vec<u8>,
+}
+
+impl Argon2Kdf {
+    /// New parameters with a random salt
+    pub fn generate(cost: Argon2Cost) -> Self {
+        let mut salt = vec![0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            cost,
+            parallelism: 1,
+            salt,
+        }
+    }
+
+    pub fn derive_key(&self, passphrase: &[u8]) -> Result<[u8; 32]> {
+        if self.cost.memory_kib > MAX_MEMORY_KIB || self.cost.iterations > MAX_ITERATIONS {
+            anyhow::bail!(
+                "Argon2 cost too high: {} KiB, {} iterations",
+                self.cost.memory_kib,
+                self.cost.iterations
+            );
+        }
+        let params = Params::new(
+            self.cost.memory_kib,
+            self.cost.iterations,
+            self.parallelism as u32,
+            Some(32),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {}", e))?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase, &self.salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Argon2 key derivation failed: {}", e))?;
+        Ok(key)
+    }
+}
+
+/// Read a passphrase from the terminal without echo, an environment
+/// variable, or the first line of a file. Prompts twice when `confirm`.
+pub fn read_passphrase(source: &PassphraseSource, confirm: bool) -> Result<String> {
+    let passphrase = match source {
+        PassphraseSource::Prompt => {
+            let passphrase = rpassword::prompt_password("Passphrase: ")?;
+            if confirm && rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+                anyhow::bail!("Passphrases don't match");
+            }
+            passphrase
+        }
+        PassphraseSource::Env(name) => std::env::var(name)
+            .map_err(|_| anyhow::anyhow!("Environment variable {} is not set", name))?,
+        PassphraseSource::File(path) => {
+            let content = fs::read_to_string(path)?;
+            content.lines().next().unwrap_or_default().to_string()
+        }
+    };
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase is empty");
+    }
+    Ok(passphrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_COST: Argon2Cost = Argon2Cost {
+        memory_kib: 256,
+        iterations: 1,
+    };
+
+    #[test]
+    fn test_argon2_derive_key() -> Result<()> {
+        let kdf = Argon2Kdf::generate(TEST_COST);
+        assert_eq!(kdf.salt.len(), SALT_SIZE);
+        let key = kdf.derive_key(b"correct horse")?;
+        assert_eq!(kdf.derive_key(b"correct horse")?, key);
+        assert_ne!(kdf.derive_key(b"battery staple")?, key);
+        assert_ne!(
+            Argon2Kdf::generate(TEST_COST).derive_key(b"correct horse")?,
+            key
+        );
+
+        let mut expensive = kdf.clone();
+        expensive.cost.memory_kib = MAX_MEMORY_KIB + 1;
+        assert!(expensive.derive_key(b"correct horse").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_passphrase() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("passphrase");
+        fs::write(&path, "correct horse\nignored\n")?;
+        let source = PassphraseSource::File(path.to_str().unwrap().to_string());
+        assert_eq!(read_passphrase(&source, true)?, "correct horse");
+        fs::write(&path, "\n")?;
+        assert!(read_passphrase(&source, false).is_err());
+
+        let source = PassphraseSource::Env("RCLI_TEST_PASSPHRASE_UNSET".to_string());
+        assert!(read_passphrase(&source, false).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    format_checksum_line, hash_file, is_signature_bundle, parse_checksum_line,
+    process_text_sign_bundle, process_text_verify, HashFormat, SignatureBundle, TextSignFormat,
+};
+use anyhow::{Ok, Result};
+use data_encoding::HEXLOWER_PERMISSIVE;
+
+// signed bundle metadata naming the hash used in the manifest
+const MANIFEST_HASH_METADATA: &str = "hash";
+// looked for in this order when no manifest is given, xxh3 isn't
+// collision resistant so it can't be used for a manifest
+const MANIFEST_HASHES: [HashFormat; 5] = [
+    HashFormat::Sha256,
+    HashFormat::Blake3,
+    HashFormat::Sha512,
+    HashFormat::Sha3_256,
+    HashFormat::Sha3_512,
+];
+
+/// Result of `process_text_verify_manifest`, paths relative to the directory
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ManifestReport {
+    pub verified: usize,
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl ManifestReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl HashFormat {
+    // default manifest name, after the ones sha256sum and b3sum users write
+    pub fn manifest_name(self) -> &'static str {
+        match self {
+            HashFormat::Blake3 => "B3SUMS",
+            HashFormat::Sha256 => "SHA256SUMS",
+            HashFormat::Sha512 => "SHA512SUMS",
+            HashFormat::Sha3_256 => "SHA3-256SUMS",
+            HashFormat::Sha3_512 => "SHA3-512SUMS",
+            HashFormat::Xxh3 => "XXH3SUMS",
+        }
+    }
+}
+
+/// Hash every file under `dir` into a `sha256sum`/`b3sum` compatible
+/// manifest, `SHA256SUMS`, `B3SUMS` etc. in `dir` unless given, and sign it
+/// into a signature bundle next to it, `<manifest>.sig`. Returns the
+/// manifest path.
+pub async fn process_text_sign_manifest(
+    dir: &Path,
+    private_key: &str,
+    format: TextSignFormat,
+    hash: HashFormat,
+    manifest: Option<&Path>,
+) -> Result<PathBuf> {
+    if !MANIFEST_HASHES.contains(&hash) {
+        anyhow::bail!(
+            "{} is not a cryptographic hash, it can't be used for a manifest",
+            hash
+        );
+    }
+    let manifest = manifest
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| dir.join(hash.manifest_name()));
+    let signature = signature_path(&manifest);
+    let skip = [absolute(&manifest)?, absolute(&signature)?];
+
+    let mut content = String::new();
+    for (name, path) in collect_files(dir, &skip)? {
+        let digest = HEXLOWER_PERMISSIVE.encode(&hash_file(&path, hash)?);
+        content.push_str(&format!("{}\n", format_checksum_line(&digest, &name)));
+    }
+    fs::write(&manifest, content)?;
+
+    let metadata = BTreeMap::from([(MANIFEST_HASH_METADATA.to_string(), hash.to_string())]);
+    let bundle =
+        process_text_sign_bundle(path_str(&manifest)?, private_key, format, metadata).await?;
+    fs::write(&signature, format!("{}\n", bundle.to_json()?))?;
+    Ok(manifest)
+}
+
+/// Check the manifest's signature bundle, then re-hash every file under
+/// `dir` against it. A bad signature is an error, file differences are
+/// reported. Without `manifest` looks for `SHA256SUMS`, then `B3SUMS` and
+/// the other `MANIFEST_HASHES`.
+pub async fn process_text_verify_manifest(
+    dir: &Path,
+    public_key: &str,
+    manifest: Option<&Path>,
+) -> Result<ManifestReport> {
+    let manifest = match manifest {
+        Some(manifest) => manifest.to_path_buf(),
+        None => MANIFEST_HASHES
+            .iter()
+            .map(|hash| dir.join(hash.manifest_name()))
+            .find(|path| path.is_file())
+            .ok_or_else(|| anyhow::anyhow!("No SHA256SUMS or B3SUMS in {}", dir.display()))?,
+    };
+    let signature = signature_path(&manifest);
+    let bundle = fs::read_to_string(&signature)
+        .map_err(|e| anyhow::anyhow!("Can't read {}: {}", signature.display(), e))?;
+    if !is_signature_bundle(&bundle) {
+        anyhow::bail!("{} is not a signature bundle", signature.display());
+    }
+    if !process_text_verify(path_str(&manifest)?, public_key, None, &bundle).await? {
+        anyhow::bail!("Signature of {} is invalid", manifest.display());
+    }
+    // the hash is signed along with the manifest
+    let hash: HashFormat = SignatureBundle::from_json(&bundle)?
+        .metadata
+        .get(MANIFEST_HASH_METADATA)
+        .ok_or_else(|| anyhow::anyhow!("Signature bundle doesn't name the manifest hash"))?
+        .parse()?;
+    if !MANIFEST_HASHES.contains(&hash) {
+        anyhow::bail!(
+            "{} is not a cryptographic hash, it can't be used for a manifest",
+            hash
+        );
+    }
+
+    let entries = parse_manifest(&fs::read_to_string(&manifest)?)?;
+    let skip = [absolute(&manifest)?, absolute(&signature)?];
+    let files = collect_files(dir, &skip)?;
+
+    let mut report = ManifestReport::default();
+    for (name, digest) in &entries {
+        let path = dir.join(name);
+        if !path.is_file() {
+            report.missing.push(name.clone());
+        } else if hash_file(&path, hash)? != *digest {
+            report.modified.push(name.clone());
+        } else {
+            report.verified += 1;
+        }
+    }
+    report.extra = files
+        .into_keys()
+        .filter(|name| !entries.contains_key(name))
+        .collect();
+    Ok(report)
+}
+
+// `<hex>  <path>` lines, with `*` in front of the path for binary mode
+fn parse_manifest(content: &str) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut entries = BTreeMap::new();
+    for (i, line) in content.lines().enumerate() {
+        let invalid = || anyhow::anyhow!("Invalid manifest line {}: {}", i + 1, line);
+        let (digest, name) = parse_checksum_line(line).ok_or_else(invalid)?;
+        let digest = HEXLOWER_PERMISSIVE
+            .decode(digest.as_bytes())
+            .map_err(|_| invalid())?;
+        // entries must stay inside the directory
+        let relative = Path::new(&*name)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+        if !relative {
+            return Err(invalid());
+        }
+        entries.insert(name.into_owned(), digest);
+    }
+    Ok(entries)
+}
+
+// regular files under `dir` by their `/` separated relative name, symlinks
+// are not followed
+fn collect_files(dir: &Path, skip: &[PathBuf]) -> Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else if file_type.is_file() && !skip.contains(&absolute(&path)?) {
+                files.insert(relative_name(dir, &path)?, path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn relative_name(dir: &Path, path: &Path) -> Result<String> {
+    let name = path
+        .strip_prefix(dir)?
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("File name is not UTF-8: {}", path.display()))?
+        .replace(std::path::MAIN_SEPARATOR, "/");
+    if name.contains('\n') {
+        anyhow::bail!("File name contains a newline: {}", path.display());
+    }
+    Ok(name)
+}
+
+fn signature_path(manifest: &Path) -> PathBuf {
+    let mut name = manifest.as_os_str().to_os_string();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+// canonical parent joined with the file name, works for files that don't
+// exist yet
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Not a file: {}", path.display()))?;
+    Ok(fs::canonicalize(parent)?.join(name))
+}
+
+fn path_str(path: &Path) -> Result<&str> {
+    path.to_str()
+        .ok_or_else(|| anyhow::anyhow!("Path is not UTF-8: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a `tree` directory with two files, inside a temporary directory
+    fn tree() -> Result<(tempfile::TempDir, PathBuf)> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path().join("tree");
+        fs::create_dir_all(dir.join("bin"))?;
+        fs::write(dir.join("README"), "readme\n")?;
+        fs::write(dir.join("bin/rcli"), "binary")?;
+        Ok((tmp, dir))
+    }
+
+    #[tokio::test]
+    async fn test_sign_verify_manifest() -> Result<()> {
+        let (_tmp, dir) = tree()?;
+        let (sk, pk) = ("fixtures/ed25519.sk", "fixtures/ed25519.pk");
+        let manifest =
+            process_text_sign_manifest(&dir, sk, TextSignFormat::Ed25519, HashFormat::Sha256, None)
+                .await?;
+        assert_eq!(manifest, dir.join("SHA256SUMS"));
+        let sha256 = |path: &str| -> Result<String> {
+            Ok(HEXLOWER_PERMISSIVE.encode(&hash_file(dir.join(path), HashFormat::Sha256)?))
+        };
+        assert_eq!(
+            fs::read_to_string(&manifest)?,
+            format!(
+                "{}  README\n{}  bin/rcli\n",
+                sha256("README")?,
+                sha256("bin/rcli")?
+            )
+        );
+        assert!(dir.join("SHA256SUMS.sig").is_file());
+
+        let report = process_text_verify_manifest(&dir, pk, None).await?;
+        assert!(report.is_ok());
+        assert_eq!(report.verified, 2);
+
+        fs::write(dir.join("README"), "changed\n")?;
+        fs::remove_file(dir.join("bin/rcli"))?;
+        fs::write(dir.join("NEW"), "new")?;
+        let report = process_text_verify_manifest(&dir, pk, None).await?;
+        assert_eq!(
+            report,
+            ManifestReport {
+                verified: 0,
+                missing: vec!["bin/rcli".to_string()],
+                extra: vec!["NEW".to_string()],
+                modified: vec!["README".to_string()],
+            }
+        );
+
+        // the manifest itself is signed
+        fs::write(&manifest, "00  README\n")?;
+        assert!(process_text_verify_manifest(&dir, pk, None).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sign_manifest_blake3_outside_dir() -> Result<()> {
+        let (_tmp, dir) = tree()?;
+        let manifest = dir.with_extension("B3SUMS");
+        process_text_sign_manifest(
+            &dir,
+            "fixtures/blake3.txt",
+            TextSignFormat::Blake3,
+            HashFormat::Blake3,
+            Some(&manifest),
+        )
+        .await?;
+        let report =
+            process_text_verify_manifest(&dir, "fixtures/blake3.txt", Some(&manifest)).await?;
+        assert!(report.is_ok());
+        assert_eq!(report.verified, 2);
+        assert!(signature_path(&manifest).is_file());
+
+        // xxh3 is fine for checksums, not for a signed manifest
+        let xxh3 = process_text_sign_manifest(
+            &dir,
+            "fixtures/blake3.txt",
+            TextSignFormat::Blake3,
+            HashFormat::Xxh3,
+            None,
+        )
+        .await;
+        assert!(xxh3.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_manifest() -> Result<()> {
+        let entries = parse_manifest("abcd  a b.txt\n01 *bin/rcli\n")?;
+        assert_eq!(entries["a b.txt"], vec![0xab, 0xcd]);
+        assert_eq!(entries["bin/rcli"], vec![0x01]);
+        assert!(parse_manifest("abcd a\n").is_err());
+        assert!(parse_manifest("xyz  a\n").is_err());
+        assert!(parse_manifest("abcd  ../etc/passwd\n").is_err());
+        assert!(parse_manifest("abcd  /etc/passwd\n").is_err());
+        Ok(())
+    }
+}
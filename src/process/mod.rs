@@ -1,16 +1,58 @@
 mod b64;
+mod codec;
+mod compress;
 mod csv_convert;
+mod ecdsa;
+mod envelope;
+mod escape;
 mod gen_pass;
+mod hash;
+mod hexdump;
 mod http_serve;
+mod inspect;
 mod jwt;
+mod kdf;
+mod key_file;
+mod manifest;
+mod signature;
 mod text;
 
-pub use b64::{process_decode, process_encode};
+pub use b64::{
+    decode_armored, decode_stream, encode_armored, encode_stream, process_decode, process_encode,
+};
+pub use codec::{
+    codec_decode_stream, codec_encode_stream, process_codec_decode, process_codec_encode, Codec,
+};
+pub use compress::{
+    compress_stream, decompress_stream, process_compress, process_decompress, process_precompress,
+};
 pub use csv_convert::process_csv;
+pub use ecdsa::EcdsaCurve;
+pub use envelope::{
+    key_id, Envelope, ENVELOPE_MAGIC, ENVELOPE_PEM_LABEL, ENVELOPE_VERSION, PROTECTED_KEY_PEM_LABEL,
+};
 pub use gen_pass::process_genpass;
+pub use hash::{
+    format_checksum_line, hash_file, hash_reader, parse_checksum_line, process_hash,
+    process_hash_check, HashCheck, HashCheckStatus,
+};
+pub use hexdump::{
+    hexdump_reverse_stream, hexdump_stream, process_hexdump, process_hexdump_reverse,
+};
 pub use http_serve::process_http_serve;
+pub use inspect::{inspect, process_inspect, render_decoded, Inspection};
 pub use jwt::{process_jwt_sign, process_jwt_verify};
+pub use kdf::{read_passphrase, Argon2Cost, Argon2Kdf};
+pub use key_file::{
+    decode_ed25519_signing_key, decode_ed25519_verifying_key, decode_key, encode_ed25519_keys,
+    is_protected_key, read_key, read_key_file, write_key_files, KeyFile,
+};
+pub use manifest::{process_text_sign_manifest, process_text_verify_manifest, ManifestReport};
+pub use signature::{
+    is_signature_bundle, key_fingerprint, SignatureBundle, SIGNATURE_BUNDLE_VERSION,
+};
 pub use text::{
-    process_text_decrypt, process_text_encrypt, process_text_key_generate, process_text_sign,
-    process_text_verify,
+    process_text_decrypt, process_text_encrypt, process_text_encrypt_file,
+    process_text_key_generate, process_text_sign, process_text_sign_bundle, process_text_verify,
+    protect_private_key, unprotect_private_key,
 };
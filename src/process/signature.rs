@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Ok, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+
+pub const SIGNATURE_BUNDLE_VERSION: u8 = 1;
+// first line of the signed message, keeps bundle signatures apart from
+// bare signatures over the same data
+const SIGNATURE_BUNDLE_CONTEXT: &[u8] = b"rcli signature bundle v1\n";
+
+/// Detached signature written by `text sign --bundle`, as JSON:
+///
+/// ```text
+/// version          SIGNATURE_BUNDLE_VERSION
+/// algorithm        TextSignFormat name, e.g. "ed25519"
+/// key_fingerprint  hex fingerprint of the verifying key, see `key_fingerprint`
+/// timestamp        RFC 3339, UTC
+/// metadata         optional string map
+/// signature        URL_SAFE_NO_PAD, the same encoding as a bare signature
+/// ```
+///
+/// Everything but the signature is signed along with the data, see
+/// `signed_prefix`, so no field can be changed without failing verification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureBundle {
+    pub version: u8,
+    pub algorithm: String,
+    pub key_fingerprint: String,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    pub signature: String,
+}
+
+// the signed fields, in the order they're serialized
+#[derive(Serialize)]
+struct SignedFields<'a> {
+    version: u8,
+    algorithm: &'a str,
+    key_fingerprint: &'a str,
+    timestamp: &'a str,
+    metadata: &'a BTreeMap<String, String>,
+}
+
+impl SignatureBundle {
+    /// A bundle stamped with the current time, the signature still empty
+    pub fn new(
+        algorithm: &str,
+        key_fingerprint: String,
+        metadata: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            version: SIGNATURE_BUNDLE_VERSION,
+            algorithm: algorithm.to_string(),
+            key_fingerprint,
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            metadata,
+            signature: String::new(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let bundle: Self = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("Invalid signature bundle: {}", e))?;
+        if bundle.version != SIGNATURE_BUNDLE_VERSION {
+            anyhow::bail!("Unsupported signature bundle version {}", bundle.version);
+        }
+        DateTime::parse_from_rfc3339(&bundle.timestamp)
+            .map_err(|e| anyhow::anyhow!("Invalid signature bundle timestamp: {}", e))?;
+        Ok(bundle)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Bytes signed in front of the data: a context line, then the signed
+    /// fields as a single line of JSON. JSON escapes newlines inside
+    /// strings, so the first newline after the context ends the header.
+    pub fn signed_prefix(&self) -> Result<Vec<u8>> {
+        let fields = SignedFields {
+            version: self.version,
+            algorithm: &self.algorithm,
+            key_fingerprint: &self.key_fingerprint,
+            timestamp: &self.timestamp,
+            metadata: &self.metadata,
+        };
+        let mut prefix = SIGNATURE_BUNDLE_CONTEXT.to_vec();
+        prefix.extend_from_slice(&serde_json::to_vec(&fields)?);
+        prefix.push(b'\n');
+        Ok(prefix)
+    }
+}
+
+// a bundle is a JSON object, a bare signature is base64
+pub fn is_signature_bundle(signature: &str) -> bool {
+    signature.trim_start().starts_with('{')
+}
+
+/// Non-secret fingerprint of a signing key, computed from the verifying
+/// key so both halves of a key pair agree. For blake3 that's the shared
+/// key itself, which is why this is a derived hash and not the key.
+pub fn key_fingerprint(verifying_key: &[u8]) -> String {
+    let hash = blake3::derive_key("rcli 2024 text sign key fingerprint", verifying_key);
+    HEXLOWER.encode(&hash[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_bundle_roundtrip() -> Result<()> {
+        let metadata = BTreeMap::from([("release".to_string(), "v1.0\nrc1".to_string())]);
+        let mut bundle = SignatureBundle::new("ed25519", key_fingerprint(b"key"), metadata);
+        bundle.signature = "c2ln".to_string();
+        assert_eq!(bundle.key_fingerprint.len(), 32);
+
+        let json = bundle.to_json()?;
+        assert!(is_signature_bundle(&json));
+        assert!(!is_signature_bundle("c2ln"));
+        let parsed = SignatureBundle::from_json(&json)?;
+        assert_eq!(parsed, bundle);
+        assert_eq!(parsed.signed_prefix()?, bundle.signed_prefix()?);
+
+        let prefix = bundle.signed_prefix()?;
+        assert!(prefix.starts_with(SIGNATURE_BUNDLE_CONTEXT));
+        assert_eq!(prefix.iter().filter(|&&b| b == b'\n').count(), 2);
+
+        let mut changed = bundle.clone();
+        changed.metadata.clear();
+        assert_ne!(changed.signed_prefix()?, prefix);
+        changed.signature = "other".to_string();
+        bundle.signature = "other".to_string();
+        assert_eq!(bundle.signed_prefix()?, prefix);
+        Ok(())
+    }
+
+    #[test]
+    fn test_signature_bundle_rejects_bad_json() {
+        let bundle = SignatureBundle::new("blake3", key_fingerprint(b"key"), BTreeMap::new());
+        let json = bundle.to_json().unwrap();
+        assert!(!json.contains("metadata"));
+        assert!(
+            SignatureBundle::from_json(&json.replace("\"version\": 1", "\"version\": 2")).is_err()
+        );
+        assert!(SignatureBundle::from_json(&json.replace(&bundle.timestamp, "yesterday")).is_err());
+        assert!(SignatureBundle::from_json("{}").is_err());
+    }
+}
@@ -1,5 +1,8 @@
 use anyhow::Result;
-use std::{fs::File, io::Read};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+};
 use uuid::Uuid;
 
 use crate::Config;
@@ -13,6 +16,81 @@ pub fn get_reader(input: &str) -> Result<Box<dyn Read>, anyhow::Error> {
     Ok(reader)
 }
 
+// buffered, callers have to flush before the writer is dropped
+pub fn get_writer(output: &str) -> Result<Box<dyn Write>, anyhow::Error> {
+    let writer: Box<dyn Write> = if output == "-" {
+        Box::new(std::io::stdout())
+    } else {
+        Box::new(BufWriter::new(File::create(output)?))
+    };
+    Ok(writer)
+}
+
+// like read_exact, but a short read at EOF is fine
+pub fn read_full(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            io::Result::Ok(0) => break,
+            io::Result::Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    io::Result::Ok(filled)
+}
+
+/// Drops ASCII whitespace, such as the line breaks of wrapped encoded
+/// text, and passes every other byte through `map`, which can replace it
+/// or fail the read.
+pub struct SkipWhitespace<R, F> {
+    inner: R,
+    map: F,
+}
+
+impl<R: Read, F: FnMut(u8) -> io::Result<u8>> SkipWhitespace<R, F> {
+    pub fn new(inner: R, map: F) -> Self {
+        Self { inner, map }
+    }
+}
+
+impl<R: Read, F: FnMut(u8) -> io::Result<u8>> Read for SkipWhitespace<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return io::Result::Ok(0);
+            }
+            let mut len = 0;
+            for i in 0..n {
+                if !buf[i].is_ascii_whitespace() {
+                    buf[len] = (self.map)(buf[i])?;
+                    len += 1;
+                }
+            }
+            // a chunk of pure whitespace is not EOF, keep reading
+            if len > 0 {
+                return io::Result::Ok(len);
+            }
+        }
+    }
+}
+
+// feeds everything written to `update`, so io::copy can fill hashers and
+// MACs that don't implement Write
+pub struct UpdateWriter<F: FnMut(&[u8])>(pub F);
+
+impl<F: FnMut(&[u8])> Write for UpdateWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (self.0)(buf);
+        io::Result::Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::Result::Ok(())
+    }
+}
+
 pub fn new_uuid() -> String {
     Uuid::new_v4().to_string()
 }